@@ -0,0 +1,27 @@
+use std::{env, fs, path::Path};
+
+/// Embeds every PNG in `assets/textures/blocks` so the game runs from any directory
+fn main() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/textures/blocks");
+    println!("cargo:rerun-if-changed={}", directory.display());
+
+    let mut textures: Vec<_> = fs::read_dir(&directory)
+        .expect("Block texture directory is missing")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "png"))
+        .collect();
+    textures.sort();
+
+    let mut source = String::from("&[\n");
+    for path in textures {
+        let name = path.file_stem().unwrap().to_string_lossy();
+        source += &format!(
+            "    ({name:?}, include_bytes!({:?})),\n",
+            path.display().to_string()
+        );
+    }
+    source += "]\n";
+
+    let output = Path::new(&env::var("OUT_DIR").unwrap()).join("block_textures.rs");
+    fs::write(output, source).unwrap();
+}
@@ -0,0 +1,222 @@
+use std::{borrow::Cow, collections::HashMap, fmt::Display, path::Path, sync::LazyLock};
+
+use image::RgbaImage;
+
+use super::generator::voxel::{BlockModel, Blocks, RegisteredBlock};
+
+#[derive(Debug, Clone)]
+pub struct AtlasError {
+    pub message: String,
+}
+
+impl AtlasError {
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl Display for AtlasError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for AtlasError {}
+type AtlasResult<T> = Result<T, AtlasError>;
+
+/// Name and PNG of every texture in `assets/textures/blocks`, generated by the build script
+static EMBEDDED: &[(&str, &[u8])] = include!(concat!(env!("OUT_DIR"), "/block_textures.rs"));
+
+static LAYOUT: LazyLock<AtlasLayout> = LazyLock::new(|| AtlasLayout::from_blocks(Blocks::BLOCKS));
+
+/// Layout of the textures of every registered block
+pub fn layout() -> &'static AtlasLayout {
+    &LAYOUT
+}
+
+/// Which layer of the terrain texture array each named texture goes in.
+///
+/// Layers are assigned in the order blocks first reference them, so the layout
+/// is known before any image is loaded. Exports pack the layers into a grid.
+#[derive(Debug, Clone)]
+pub struct AtlasLayout {
+    names: Vec<&'static str>,
+    indices: HashMap<&'static str, usize>,
+    /// Texture indices of every block by id, in `RegisteredBlock::textures` order
+    blocks: Vec<[usize; 6]>,
+    columns: usize,
+    rows: usize,
+}
+
+#[allow(dead_code)]
+impl AtlasLayout {
+    pub fn from_blocks(blocks: &[RegisteredBlock]) -> Self {
+        let mut names = Vec::new();
+        let mut indices = HashMap::new();
+
+        let blocks = blocks
+            .iter()
+            .map(|block| {
+                // Blocks that are never meshed don't need textures
+                if block.model == BlockModel::None {
+                    return [0; 6];
+                }
+
+                block.textures.map(|name| {
+                    *indices.entry(name).or_insert_with(|| {
+                        names.push(name);
+                        names.len() - 1
+                    })
+                })
+            })
+            .collect();
+
+        let columns = (names.len() as f32).sqrt().ceil().max(1.0) as usize;
+        let rows = names.len().div_ceil(columns).max(1);
+
+        Self {
+            names,
+            indices,
+            blocks,
+            columns,
+            rows,
+        }
+    }
+
+    pub fn names(&self) -> &[&'static str] {
+        &self.names
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).copied()
+    }
+
+    pub fn block_textures(&self, block_id: usize) -> [usize; 6] {
+        self.blocks[block_id]
+    }
+
+    /// Columns and rows of the packed grid
+    pub fn grid(&self) -> (usize, usize) {
+        (self.columns, self.rows)
+    }
+
+    /// Size of one texture in UV space of the packed grid
+    pub fn uv_step(&self) -> (f32, f32) {
+        (1.0 / self.columns as f32, 1.0 / self.rows as f32)
+    }
+
+    /// UV of the top left corner of the texture at `index`
+    pub fn uv_offset(&self, index: usize) -> (f32, f32) {
+        let step = self.uv_step();
+
+        (
+            (index % self.columns) as f32 * step.0,
+            (index / self.columns) as f32 * step.1,
+        )
+    }
+
+    /// Loads `<name>.png` for every texture in the layout from `directory`,
+    /// in layer order.
+    ///
+    /// Fails listing every missing texture, all textures must be the same size.
+    pub fn load(&self, directory: impl AsRef<Path>) -> AtlasResult<Vec<RgbaImage>> {
+        let directory = directory.as_ref();
+
+        self.decode(|name| {
+            let path = directory.join(format!("{name}.png"));
+            let bytes = std::fs::read(&path).ok().map(Cow::Owned);
+
+            (path.display().to_string(), bytes)
+        })
+    }
+
+    /// Like `load`, with the textures built into the binary
+    pub fn load_embedded(&self) -> AtlasResult<Vec<RgbaImage>> {
+        self.decode(|name| {
+            let bytes = EMBEDDED
+                .iter()
+                .find(|(embedded, _)| *embedded == name)
+                .map(|(_, bytes)| Cow::Borrowed(*bytes));
+
+            (format!("{name}.png"), bytes)
+        })
+    }
+
+    /// Decodes every texture in layer order, `read` gives where a texture
+    /// comes from and its PNG, if there is one.
+    fn decode<'a>(
+        &self,
+        read: impl Fn(&str) -> (String, Option<Cow<'a, [u8]>>),
+    ) -> AtlasResult<Vec<RgbaImage>> {
+        let files: Vec<_> = self.names.iter().map(|name| read(name)).collect();
+
+        let missing: Vec<_> = files
+            .iter()
+            .filter(|(_, bytes)| bytes.is_none())
+            .map(|(source, _)| source.as_str())
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(AtlasError::new(format!(
+                "Missing block textures: {}",
+                missing.join(", ")
+            )));
+        }
+
+        let mut images = Vec::with_capacity(self.names.len());
+        for (source, bytes) in files {
+            let image = image::load_from_memory(&bytes.unwrap_or_default())
+                .map_err(|e| AtlasError::new(format!("Failed to load {source}: {e}")))?
+                .to_rgba8();
+
+            images.push(image);
+        }
+
+        let tile_size = images.first().map_or((1, 1), |image| image.dimensions());
+        if let Some((name, image)) = self
+            .names
+            .iter()
+            .zip(&images)
+            .find(|(_, image)| image.dimensions() != tile_size)
+        {
+            return Err(AtlasError::new(format!(
+                "Texture {name} is {}x{}, expected {}x{} like the others",
+                image.width(),
+                image.height(),
+                tile_size.0,
+                tile_size.1
+            )));
+        }
+
+        Ok(images)
+    }
+
+    /// Packs textures loaded by `load` into a grid, for tools without texture arrays
+    pub fn pack(&self, textures: &[RgbaImage]) -> RgbaImage {
+        let tile_size = textures.first().map_or((1, 1), |image| image.dimensions());
+
+        let mut atlas = RgbaImage::new(
+            tile_size.0 * self.columns as u32,
+            tile_size.1 * self.rows as u32,
+        );
+
+        for (index, image) in textures.iter().enumerate() {
+            image::imageops::replace(
+                &mut atlas,
+                image,
+                ((index % self.columns) as u32 * tile_size.0) as i64,
+                ((index / self.columns) as u32 * tile_size.1) as i64,
+            );
+        }
+
+        atlas
+    }
+
+    /// Maps `uv` within the texture at `layer` to UVs of the packed atlas
+    pub fn atlas_uv(&self, layer: u32, uv: [f32; 2]) -> [f32; 2] {
+        let offset = self.uv_offset(layer as usize);
+        let step = self.uv_step();
+
+        [offset.0 + uv[0] * step.0, offset.1 + uv[1] * step.1]
+    }
+}
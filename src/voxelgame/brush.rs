@@ -0,0 +1,355 @@
+use super::{
+    debug::{DebugDrawer, ModelName},
+    generator::{
+        chunk::{BlockOffsetCoord, WorldCoord},
+        edit::WorldEdit,
+        raycast::RayHit,
+        voxel::{Blocks, Voxel},
+        World,
+    },
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BrushShape {
+    Sphere,
+    Cube,
+    /// Vertical cylinder as tall as it is wide
+    Cylinder,
+    /// Horizontal one block thick circle
+    Disk,
+}
+
+impl BrushShape {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Sphere => Self::Cube,
+            Self::Cube => Self::Cylinder,
+            Self::Cylinder => Self::Disk,
+            Self::Disk => Self::Sphere,
+        }
+    }
+
+    fn contains(self, offset: BlockOffsetCoord, radius: i32) -> bool {
+        let horizontal = offset.x * offset.x + offset.z * offset.z;
+
+        match self {
+            Self::Sphere => horizontal + offset.y * offset.y <= radius * radius,
+            Self::Cube => true,
+            Self::Cylinder => horizontal <= radius * radius,
+            Self::Disk => horizontal <= radius * radius && offset.y == 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BrushMode {
+    /// Fills empty space with the brush block
+    Place,
+    /// Removes everything
+    Erase,
+    /// Swaps the target block for the brush block
+    Replace,
+    /// Recolors exposed surface blocks
+    Paint,
+    /// Removes lone bumps and fills small holes
+    Smooth,
+}
+
+impl BrushMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Place => Self::Erase,
+            Self::Erase => Self::Replace,
+            Self::Replace => Self::Paint,
+            Self::Paint => Self::Smooth,
+            Self::Smooth => Self::Place,
+        }
+    }
+
+    fn preview_color(self) -> cgmath::Vector4<f32> {
+        match self {
+            Self::Place => cgmath::Vector4::new(0.2, 1.0, 0.2, 0.6),
+            Self::Erase => cgmath::Vector4::new(1.0, 0.2, 0.2, 0.6),
+            Self::Replace => cgmath::Vector4::new(1.0, 1.0, 0.2, 0.6),
+            Self::Paint => cgmath::Vector4::new(0.2, 0.6, 1.0, 0.6),
+            Self::Smooth => cgmath::Vector4::new(1.0, 1.0, 1.0, 0.6),
+        }
+    }
+}
+
+/// Copy of the voxels around a region, `None` for voxels in unloaded chunks
+struct VoxelSnapshot {
+    min: WorldCoord,
+    size: (i32, i32, i32),
+    voxels: Vec<Option<Voxel>>,
+}
+
+impl VoxelSnapshot {
+    fn take<T>(world: &World<T>, min: WorldCoord, max: WorldCoord) -> Self {
+        let size = (max.x - min.x + 1, max.y - min.y + 1, max.z - min.z + 1);
+        let mut snapshot = Self {
+            min,
+            size,
+            voxels: vec![None; (size.0 * size.1 * size.2) as usize],
+        };
+
+        world.for_each_voxel_in_region(min, max, |coord, voxel| {
+            let index = snapshot.index(coord - min);
+            snapshot.voxels[index] = Some(voxel);
+        });
+
+        snapshot
+    }
+
+    fn get(&self, coord: WorldCoord) -> Option<Voxel> {
+        let offset = coord - self.min;
+
+        if offset.x < 0
+            || offset.y < 0
+            || offset.z < 0
+            || offset.x >= self.size.0
+            || offset.y >= self.size.1
+            || offset.z >= self.size.2
+        {
+            return None;
+        }
+
+        self.voxels[self.index(offset)]
+    }
+
+    fn index(&self, offset: BlockOffsetCoord) -> usize {
+        (offset.x + offset.y * self.size.0 + offset.z * self.size.0 * self.size.1) as usize
+    }
+}
+
+fn is_air(voxel: Voxel) -> bool {
+    voxel == Blocks::AIR.default_state()
+}
+
+fn is_solid(voxel: Voxel) -> bool {
+    Blocks::BLOCKS[voxel.id as usize].solid
+}
+
+/// Everything the edit of a brush depends on
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct PreviewKey {
+    center: WorldCoord,
+    shape: BrushShape,
+    mode: BrushMode,
+    radius: u32,
+    block: Voxel,
+    target: Voxel,
+    /// `World::revision` the edit was computed at
+    revision: u64,
+}
+
+pub struct Brush {
+    pub shape: BrushShape,
+    pub mode: BrushMode,
+    pub radius: u32,
+    /// Block placed by the brush
+    pub block: Voxel,
+    /// Block affected in `BrushMode::Replace`
+    pub target: Voxel,
+    /// Last previewed edit, recomputed only when something it depends on changes
+    preview: Option<(PreviewKey, WorldEdit)>,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            shape: BrushShape::Sphere,
+            mode: BrushMode::Erase,
+            radius: 4,
+            block: Blocks::DIRT_BLOCK.default_state(),
+            target: Blocks::STONE.default_state(),
+            preview: None,
+        }
+    }
+}
+
+impl Brush {
+    pub const MAX_RADIUS: u32 = 32;
+    /// Above this many changed voxels only the bounds are previewed
+    const PREVIEW_LIMIT: usize = 2048;
+
+    pub fn grow(&mut self) {
+        self.radius = (self.radius + 1).min(Self::MAX_RADIUS);
+    }
+
+    pub fn shrink(&mut self) {
+        self.radius = self.radius.saturating_sub(1);
+    }
+
+    /// Selects the next placeable block
+    pub fn next_block(&mut self) {
+        let next = (self.block.id as usize + 1) % Blocks::BLOCKS.len();
+        self.block = Blocks::BLOCKS[next.max(1)].default_state();
+    }
+
+    /// Voxel the brush is centered on when aiming at `hit`
+    pub fn center(&self, hit: &RayHit) -> WorldCoord {
+        match self.mode {
+            BrushMode::Place => hit.coord + hit.normal,
+            _ => hit.coord,
+        }
+    }
+
+    fn bounds(&self, center: WorldCoord) -> (WorldCoord, WorldCoord) {
+        let r = self.radius as i32;
+
+        let corner = BlockOffsetCoord { x: r, y: r, z: r };
+
+        (center + -corner, center + corner)
+    }
+
+    /// Computes the writes the brush would make around `center`
+    /// without touching the world.
+    pub fn edit<T>(&self, world: &World<T>, center: WorldCoord) -> WorldEdit {
+        let r = self.radius as i32;
+        let (min, max) = self.bounds(center);
+
+        // One voxel margin so that neighbours of the outermost voxels are known
+        let margin = BlockOffsetCoord { x: 1, y: 1, z: 1 };
+        let snapshot = VoxelSnapshot::take(world, min + -margin, max + margin);
+
+        let mut edit = WorldEdit::new();
+
+        for x in -r..=r {
+            for y in -r..=r {
+                for z in -r..=r {
+                    let offset = BlockOffsetCoord { x, y, z };
+
+                    if !self.shape.contains(offset, r) {
+                        continue;
+                    }
+
+                    let coord = center + offset;
+                    let Some(current) = snapshot.get(coord) else {
+                        continue;
+                    };
+
+                    if let Some(voxel) = self.apply(&snapshot, coord, current) {
+                        if voxel != current {
+                            edit.set(coord, voxel);
+                        }
+                    }
+                }
+            }
+        }
+
+        edit
+    }
+
+    /// New value of the voxel at `coord`, if the brush changes it
+    fn apply(&self, snapshot: &VoxelSnapshot, coord: WorldCoord, current: Voxel) -> Option<Voxel> {
+        match self.mode {
+            BrushMode::Place => is_air(current).then_some(self.block),
+            BrushMode::Erase => Some(Blocks::AIR.default_state()),
+            BrushMode::Replace => (current == self.target).then_some(self.block),
+            BrushMode::Paint => {
+                let exposed = [
+                    coord.left(),
+                    coord.right(),
+                    coord.up(),
+                    coord.down(),
+                    coord.front(),
+                    coord.back(),
+                ]
+                .into_iter()
+                .any(|c| snapshot.get(c).is_some_and(is_air));
+
+                (!is_air(current) && exposed).then_some(self.block)
+            }
+            BrushMode::Smooth => {
+                let mut solid = 0;
+                let mut counts = [0u8; Blocks::BLOCKS.len()];
+
+                for x in -1..=1 {
+                    for y in -1..=1 {
+                        for z in -1..=1 {
+                            if x == 0 && y == 0 && z == 0 {
+                                continue;
+                            }
+
+                            let neighbour = snapshot
+                                .get(coord + BlockOffsetCoord { x, y, z })
+                                .unwrap_or(current);
+
+                            if is_solid(neighbour) {
+                                solid += 1;
+                                counts[neighbour.id as usize] += 1;
+                            }
+                        }
+                    }
+                }
+
+                // Out of 26 neighbours
+                if is_solid(current) && solid < 9 {
+                    Some(Blocks::AIR.default_state())
+                } else if !is_solid(current) && solid > 17 {
+                    let most_common = (0..counts.len()).max_by_key(|i| counts[*i]).unwrap();
+                    Some(Blocks::BLOCKS[most_common].default_state())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Draws the voxels the brush would change around `center`, or just the brush bounds
+    /// if there are too many of them.
+    pub fn append_preview<T>(
+        &mut self,
+        debug: &mut DebugDrawer,
+        world: &World<T>,
+        center: WorldCoord,
+    ) {
+        let key = PreviewKey {
+            center,
+            shape: self.shape,
+            mode: self.mode,
+            radius: self.radius,
+            block: self.block,
+            target: self.target,
+            revision: world.revision(),
+        };
+
+        if self
+            .preview
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != key)
+        {
+            self.preview = Some((key, self.edit(world, center)));
+        }
+        let Some((_, edit)) = &self.preview else {
+            return;
+        };
+
+        let color = self.mode.preview_color();
+
+        if edit.len() > Self::PREVIEW_LIMIT {
+            let (min, _) = self.bounds(center);
+            let size = (self.radius * 2 + 1) as f32;
+
+            debug.append_mesh(
+                ModelName::Cube,
+                min.into(),
+                cgmath::Vector3::new(size, size, size),
+                color,
+            );
+            return;
+        }
+
+        for (coord, _) in edit.iter() {
+            let position: cgmath::Vector3<f32> = coord.into();
+
+            debug.append_mesh(
+                ModelName::Cube,
+                position + cgmath::Vector3::new(0.05, 0.05, 0.05),
+                cgmath::Vector3::new(0.9, 0.9, 0.9),
+                color,
+            );
+        }
+    }
+}
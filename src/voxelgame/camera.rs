@@ -1,7 +1,7 @@
 use std::f32::consts::FRAC_PI_2;
 
 use bytemuck::{Pod, Zeroable};
-use cgmath::{InnerSpace, Matrix3, Matrix4, Point3, SquareMatrix, Vector3};
+use cgmath::{InnerSpace, Matrix, Matrix3, Matrix4, Point3, SquareMatrix, Vector3, Vector4};
 use winit::{
     event::{DeviceEvent, KeyEvent, WindowEvent},
     keyboard::KeyCode,
@@ -54,6 +54,11 @@ impl Camera {
         )
     }
 
+    /// Frustum of the same view projection the shaders get from `uniform`
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(self.projection(self.aspect) * self.view())
+    }
+
     pub fn uniform(&self) -> CameraUniform {
         let view = self.view();
 
@@ -73,6 +78,54 @@ pub struct CameraUniform {
     projection: [[f32; 4]; 4],
 }
 
+/// Planes bounding what a camera sees, `xyz` is the normal pointing inside
+/// and `w` the distance so that `dot(normal, point) + w >= 0` inside
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum {
+    planes: [Vector4<f32>; 6],
+}
+
+#[allow(dead_code)]
+impl Frustum {
+    /// Extracts the planes of a view projection matrix
+    pub fn from_matrix(matrix: Matrix4<f32>) -> Self {
+        let rows = [0, 1, 2, 3].map(|i| matrix.row(i));
+
+        // Left, right, bottom, top, near and far
+        let planes = [
+            rows[3] + rows[0],
+            rows[3] - rows[0],
+            rows[3] + rows[1],
+            rows[3] - rows[1],
+            rows[3] + rows[2],
+            rows[3] - rows[2],
+        ]
+        .map(|plane| plane / plane.truncate().magnitude());
+
+        Self { planes }
+    }
+
+    pub fn planes(&self) -> &[Vector4<f32>; 6] {
+        &self.planes
+    }
+
+    /// Whether any of the box between `min` and `max` can be inside.
+    ///
+    /// Conservative, boxes near the corners of the frustum may pass while outside.
+    pub fn intersects_aabb(&self, min: Vector3<f32>, max: Vector3<f32>) -> bool {
+        self.planes.iter().all(|plane| {
+            // Corner of the box furthest along the plane's normal
+            let corner = Vector3::new(
+                if plane.x >= 0.0 { max.x } else { min.x },
+                if plane.y >= 0.0 { max.y } else { min.y },
+                if plane.z >= 0.0 { max.z } else { min.z },
+            );
+
+            plane.truncate().dot(corner) + plane.w >= 0.0
+        })
+    }
+}
+
 pub struct Axis {
     negative_pressed: bool,
     negative_button: KeyCode,
@@ -164,11 +217,33 @@ impl CameraController {
         }
     }
 
-    pub fn update(&mut self, camera: &mut Camera, delta: f32) {
+    pub fn rotate(&self, camera: &mut Camera) {
         let roty = Matrix3::from_axis_angle(Vector3::unit_y(), cgmath::Rad(self.camera_motion.0));
         let rotx = Matrix3::from_axis_angle(Vector3::unit_x(), cgmath::Rad(self.camera_motion.1));
 
         camera.direction = roty * rotx * Vector3::unit_z();
+    }
+
+    /// Horizontal direction of movement keys relative to the camera,
+    /// normalized or zero if no keys are held.
+    pub fn walk_direction(&self, camera: &Camera) -> Vector3<f32> {
+        let movement =
+            self.horizontal.get() * camera.right() + self.vertical.get() * camera.direction;
+        let movement = Vector3::new(movement.x, 0.0, movement.z);
+
+        if movement.magnitude2() == 0.0 {
+            return movement;
+        }
+
+        movement.normalize()
+    }
+
+    pub fn jump_held(&self) -> bool {
+        self.updown_axis.get() > 0.0
+    }
+
+    pub fn update(&mut self, camera: &mut Camera, delta: f32) {
+        self.rotate(camera);
 
         let movement = (self.horizontal.get() * camera.right()
             + self.vertical.get() * camera.direction
@@ -0,0 +1,608 @@
+use std::{collections::HashMap, ops::Range};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DrawIndexedIndirectArgs;
+
+use super::{
+    generator::chunk::ChunkCoord,
+    mesh::{ChunkVertex, MeshInfo},
+};
+
+/// First fit allocator of ranges within a buffer of `capacity` elements
+#[derive(Clone, Debug)]
+pub struct RangeAllocator {
+    capacity: u64,
+    /// Sorted and never adjacent, touching ranges are merged
+    free: Vec<Range<u64>>,
+}
+
+#[allow(dead_code)]
+impl RangeAllocator {
+    pub fn new(capacity: u64) -> Self {
+        Self {
+            capacity,
+            free: std::iter::once(0..capacity).collect(),
+        }
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    pub fn free_ranges(&self) -> &[Range<u64>] {
+        &self.free
+    }
+
+    pub fn free_space(&self) -> u64 {
+        self.free.iter().map(|range| range.end - range.start).sum()
+    }
+
+    pub fn largest_free(&self) -> u64 {
+        self.free
+            .iter()
+            .map(|range| range.end - range.start)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn allocate(&mut self, size: u64) -> Option<Range<u64>> {
+        let index = self
+            .free
+            .iter()
+            .position(|range| range.end - range.start >= size)?;
+
+        let range = &mut self.free[index];
+        let allocated = range.start..range.start + size;
+        range.start += size;
+
+        if range.is_empty() {
+            self.free.remove(index);
+        }
+
+        Some(allocated)
+    }
+
+    pub fn free(&mut self, range: Range<u64>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let index = self.free.partition_point(|free| free.start < range.start);
+        self.free.insert(index, range);
+
+        // Merge with the following range, then with the preceding one
+        if index + 1 < self.free.len() && self.free[index].end == self.free[index + 1].start {
+            self.free[index].end = self.free.remove(index + 1).end;
+        }
+        if index > 0 && self.free[index - 1].end == self.free[index].start {
+            self.free[index - 1].end = self.free.remove(index).end;
+        }
+    }
+
+    /// Where each allocation of `sizes` ends up when packed from the start,
+    /// in the given order, leaving a single free range after them.
+    ///
+    /// Grows the capacity by doubling until `extra` more elements fit too.
+    pub fn compact(&mut self, sizes: &[u64], extra: u64) -> Vec<Range<u64>> {
+        let used: u64 = sizes.iter().sum();
+        while used + extra > self.capacity {
+            self.capacity = (self.capacity * 2).max(1);
+        }
+
+        let mut start = 0;
+        let ranges = sizes
+            .iter()
+            .map(|size| {
+                start += size;
+                start - size..start
+            })
+            .collect();
+
+        self.free = std::iter::once(used..self.capacity)
+            .filter(|range| !range.is_empty())
+            .collect();
+
+        ranges
+    }
+}
+
+/// Per chunk entry of the storage buffer, indexed by the instance index
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Zeroable, Pod)]
+pub struct ChunkData {
+    origin: [f32; 3],
+    /// First vertex in the shared vertex buffer
+    vertex_offset: u32,
+    /// First index in the shared index buffer
+    index_offset: u32,
+    index_count: u32,
+    _padding: [u32; 2],
+}
+
+/// Where a chunk's mesh lives in the shared buffers
+#[derive(Clone, Debug)]
+pub struct ChunkAllocation {
+    pub vertices: Range<u64>,
+    pub indices: Range<u64>,
+    /// Entry in the chunk data storage buffer
+    pub slot: u32,
+    /// `MeshInfo::face_ranges` of the mesh, relative to the start of `indices`
+    pub face_ranges: [Range<u32>; 6],
+}
+
+/// A large GPU buffer sub-allocated into ranges of `element_size` bytes
+struct PooledBuffer {
+    buffer: wgpu::Buffer,
+    allocator: RangeAllocator,
+    element_size: u64,
+    usage: wgpu::BufferUsages,
+    label: &'static str,
+}
+
+impl PooledBuffer {
+    fn new(
+        device: &wgpu::Device,
+        capacity: u64,
+        element_size: u64,
+        usage: wgpu::BufferUsages,
+        label: &'static str,
+    ) -> Self {
+        let usage = usage | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC;
+
+        Self {
+            buffer: Self::create_buffer(device, capacity * element_size, usage, label),
+            allocator: RangeAllocator::new(capacity),
+            element_size,
+            usage,
+            label,
+        }
+    }
+
+    fn create_buffer(
+        device: &wgpu::Device,
+        size: u64,
+        usage: wgpu::BufferUsages,
+        label: &'static str,
+    ) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size,
+            usage,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Moves every live range to the start of a new buffer, grown if `extra`
+    /// elements still wouldn't fit, and updates the ranges to match
+    fn compact(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        mut live: Vec<&mut Range<u64>>,
+        extra: u64,
+    ) {
+        live.sort_by_key(|range| range.start);
+        let sizes: Vec<_> = live.iter().map(|range| range.end - range.start).collect();
+        let packed = self.allocator.compact(&sizes, extra);
+
+        let buffer = Self::create_buffer(
+            device,
+            self.allocator.capacity() * self.element_size,
+            self.usage,
+            self.label,
+        );
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("chunk_buffer_compaction"),
+        });
+        for (range, new_range) in live.iter_mut().zip(packed) {
+            encoder.copy_buffer_to_buffer(
+                &self.buffer,
+                range.start * self.element_size,
+                &buffer,
+                new_range.start * self.element_size,
+                (range.end - range.start) * self.element_size,
+            );
+            **range = new_range;
+        }
+        queue.submit([encoder.finish()]);
+
+        log::debug!(
+            "Compacted {} to {} KiB",
+            self.label,
+            self.allocator.capacity() * self.element_size / 1024
+        );
+
+        self.buffer = buffer;
+    }
+}
+
+/// How `ChunkBuffers::draw` issues the draws of visible chunks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkDrawMode {
+    /// A single `multi_draw_indexed_indirect` over the indirect buffer
+    MultiDrawIndirect,
+    /// One `draw_indexed` for each chunk
+    Loop,
+}
+
+impl ChunkDrawMode {
+    /// Features to request so that multi draw can be used when the adapter has them
+    pub const FEATURES: wgpu::Features = wgpu::Features::INDIRECT_FIRST_INSTANCE;
+
+    /// Chunk data is looked up with the first instance,
+    /// which indirect draws can only set with `INDIRECT_FIRST_INSTANCE`
+    pub fn supported(adapter: &wgpu::Adapter, device: &wgpu::Device) -> Self {
+        let indirect = adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::INDIRECT_EXECUTION);
+
+        if indirect && device.features().contains(Self::FEATURES) {
+            Self::MultiDrawIndirect
+        } else {
+            Self::Loop
+        }
+    }
+}
+
+/// Chunk meshes sub-allocated from shared vertex and index buffers.
+///
+/// Positions and offsets of every chunk are kept in one storage buffer,
+/// so chunks are drawn with the instance index pointing at their entry
+/// instead of binding buffers for each of them.
+pub struct ChunkBuffers {
+    vertices: PooledBuffer,
+    indices: PooledBuffer,
+    chunk_data: wgpu::Buffer,
+    slot_capacity: u32,
+    free_slots: Vec<u32>,
+    next_slot: u32,
+    bind_group: wgpu::BindGroup,
+    allocations: HashMap<ChunkCoord, ChunkAllocation>,
+    indirect: wgpu::Buffer,
+    /// Draws of the visible chunks, rebuilt every frame
+    draws: Vec<DrawIndexedIndirectArgs>,
+}
+
+#[allow(dead_code)]
+impl ChunkBuffers {
+    const INITIAL_VERTICES: u64 = 1 << 20;
+    const INITIAL_INDICES: u64 = 1 << 21;
+    const INITIAL_SLOTS: u32 = 1024;
+    const INITIAL_DRAWS: u64 = 1024;
+
+    pub fn new(device: &wgpu::Device, bind_layout: &wgpu::BindGroupLayout) -> Self {
+        let chunk_data = Self::create_chunk_data(device, Self::INITIAL_SLOTS);
+        let bind_group = Self::create_bind_group(device, bind_layout, &chunk_data);
+
+        Self {
+            vertices: PooledBuffer::new(
+                device,
+                Self::INITIAL_VERTICES,
+                std::mem::size_of::<ChunkVertex>() as u64,
+                wgpu::BufferUsages::VERTEX,
+                "chunk_vertices",
+            ),
+            indices: PooledBuffer::new(
+                device,
+                Self::INITIAL_INDICES,
+                std::mem::size_of::<u32>() as u64,
+                wgpu::BufferUsages::INDEX,
+                "chunk_indices",
+            ),
+            chunk_data,
+            slot_capacity: Self::INITIAL_SLOTS,
+            free_slots: Vec::new(),
+            next_slot: 0,
+            bind_group,
+            allocations: HashMap::new(),
+            indirect: Self::create_indirect(device, Self::INITIAL_DRAWS),
+            draws: Vec::new(),
+        }
+    }
+
+    fn create_chunk_data(device: &wgpu::Device, slots: u32) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("chunk_data"),
+            size: slots as u64 * std::mem::size_of::<ChunkData>() as u64,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_indirect(device: &wgpu::Device, draws: u64) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("chunk_draws"),
+            size: draws * std::mem::size_of::<DrawIndexedIndirectArgs>() as u64,
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        bind_layout: &wgpu::BindGroupLayout,
+        chunk_data: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("chunk_data_bind_group"),
+            layout: bind_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: chunk_data.as_entire_binding(),
+            }],
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.allocations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allocations.is_empty()
+    }
+
+    pub fn contains(&self, coord: &ChunkCoord) -> bool {
+        self.allocations.contains_key(coord)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ChunkCoord, &ChunkAllocation)> {
+        self.allocations.iter()
+    }
+
+    /// Bytes in use and allocated of the vertex and index buffers together
+    pub fn memory(&self) -> (u64, u64) {
+        let used = |pool: &PooledBuffer| {
+            (pool.allocator.capacity() - pool.allocator.free_space()) * pool.element_size
+        };
+        let total = |pool: &PooledBuffer| pool.allocator.capacity() * pool.element_size;
+
+        (
+            used(&self.vertices) + used(&self.indices),
+            total(&self.vertices) + total(&self.indices),
+        )
+    }
+
+    /// Uploads the mesh of the chunk at `coord`, replacing any previous one
+    pub fn insert(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bind_layout: &wgpu::BindGroupLayout,
+        coord: ChunkCoord,
+        mesh: &MeshInfo<ChunkVertex>,
+    ) {
+        let slot = match self.allocations.remove(&coord) {
+            Some(previous) => {
+                self.vertices.allocator.free(previous.vertices);
+                self.indices.allocator.free(previous.indices);
+                previous.slot
+            }
+            None => self.allocate_slot(device, queue, bind_layout),
+        };
+
+        let vertex_count = mesh.vertices.len() as u64;
+        let vertices = match self.vertices.allocator.allocate(vertex_count) {
+            Some(range) => range,
+            None => {
+                let live = self
+                    .allocations
+                    .values_mut()
+                    .map(|a| &mut a.vertices)
+                    .collect();
+                self.vertices.compact(device, queue, live, vertex_count);
+                self.write_all_chunk_data(queue);
+                self.vertices.allocator.allocate(vertex_count).unwrap()
+            }
+        };
+
+        let index_count = mesh.indices.len() as u64;
+        let indices = match self.indices.allocator.allocate(index_count) {
+            Some(range) => range,
+            None => {
+                let live = self
+                    .allocations
+                    .values_mut()
+                    .map(|a| &mut a.indices)
+                    .collect();
+                self.indices.compact(device, queue, live, index_count);
+                self.write_all_chunk_data(queue);
+                self.indices.allocator.allocate(index_count).unwrap()
+            }
+        };
+
+        queue.write_buffer(
+            &self.vertices.buffer,
+            vertices.start * self.vertices.element_size,
+            bytemuck::cast_slice(&mesh.vertices),
+        );
+        queue.write_buffer(
+            &self.indices.buffer,
+            indices.start * self.indices.element_size,
+            bytemuck::cast_slice(&mesh.indices),
+        );
+
+        let allocation = ChunkAllocation {
+            vertices,
+            indices,
+            slot,
+            face_ranges: mesh.face_ranges.clone(),
+        };
+        self.write_chunk_data(queue, coord, &allocation);
+        self.allocations.insert(coord, allocation);
+    }
+
+    /// Frees the space of the chunk at `coord`
+    pub fn remove(&mut self, coord: &ChunkCoord) {
+        if let Some(allocation) = self.allocations.remove(coord) {
+            self.vertices.allocator.free(allocation.vertices);
+            self.indices.allocator.free(allocation.indices);
+            self.free_slots.push(allocation.slot);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        let coords: Vec<_> = self.allocations.keys().copied().collect();
+        coords.iter().for_each(|coord| self.remove(coord));
+    }
+
+    fn allocate_slot(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bind_layout: &wgpu::BindGroupLayout,
+    ) -> u32 {
+        if let Some(slot) = self.free_slots.pop() {
+            return slot;
+        }
+
+        if self.next_slot == self.slot_capacity {
+            let slot_capacity = self.slot_capacity * 2;
+            let chunk_data = Self::create_chunk_data(device, slot_capacity);
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("chunk_data_grow"),
+            });
+            encoder.copy_buffer_to_buffer(
+                &self.chunk_data,
+                0,
+                &chunk_data,
+                0,
+                self.chunk_data.size(),
+            );
+            queue.submit([encoder.finish()]);
+
+            self.bind_group = Self::create_bind_group(device, bind_layout, &chunk_data);
+            self.chunk_data = chunk_data;
+            self.slot_capacity = slot_capacity;
+        }
+
+        self.next_slot += 1;
+        self.next_slot - 1
+    }
+
+    fn chunk_data(coord: ChunkCoord, allocation: &ChunkAllocation) -> ChunkData {
+        let origin: cgmath::Vector3<f32> = coord.into();
+
+        ChunkData {
+            origin: origin.into(),
+            vertex_offset: allocation.vertices.start as u32,
+            index_offset: allocation.indices.start as u32,
+            index_count: (allocation.indices.end - allocation.indices.start) as u32,
+            _padding: [0; 2],
+        }
+    }
+
+    fn write_chunk_data(
+        &self,
+        queue: &wgpu::Queue,
+        coord: ChunkCoord,
+        allocation: &ChunkAllocation,
+    ) {
+        queue.write_buffer(
+            &self.chunk_data,
+            allocation.slot as u64 * std::mem::size_of::<ChunkData>() as u64,
+            bytemuck::cast_slice(&[Self::chunk_data(coord, allocation)]),
+        );
+    }
+
+    /// Rewrites the offsets of every chunk after the buffers were compacted
+    fn write_all_chunk_data(&self, queue: &wgpu::Queue) {
+        for (coord, allocation) in self.allocations.iter() {
+            self.write_chunk_data(queue, *coord, allocation);
+        }
+    }
+
+    /// Draws every chunk `visible` returns the face directions to draw of,
+    /// in `ChunkVertex::NORMALS` order. Returns how many chunks were drawn.
+    ///
+    /// Faces seen from every direction are drawn for all visible chunks.
+    pub fn draw(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_pass: &mut wgpu::RenderPass,
+        mode: ChunkDrawMode,
+        visible: impl Fn(ChunkCoord) -> Option<[bool; 6]>,
+    ) -> usize {
+        self.draws.clear();
+        let mut chunks = 0;
+
+        for (coord, allocation) in self.allocations.iter() {
+            let Some(directions) = visible(*coord) else {
+                continue;
+            };
+            chunks += 1;
+
+            let index_count = (allocation.indices.end - allocation.indices.start) as u32;
+            let any_direction = allocation.face_ranges[5].end..index_count;
+            let ranges = allocation
+                .face_ranges
+                .iter()
+                .zip(directions)
+                .filter(|(_, drawn)| *drawn)
+                .map(|(range, _)| range.clone())
+                .chain(std::iter::once(any_direction))
+                .filter(|range| !range.is_empty());
+
+            for range in ranges {
+                let first_index = allocation.indices.start as u32 + range.start;
+
+                // Directions next to each other in the index buffer are drawn together
+                if let Some(last) = self.draws.last_mut().filter(|last| {
+                    last.first_instance == allocation.slot
+                        && last.first_index + last.index_count == first_index
+                }) {
+                    last.index_count += range.end - range.start;
+                    continue;
+                }
+
+                self.draws.push(DrawIndexedIndirectArgs {
+                    index_count: range.end - range.start,
+                    instance_count: 1,
+                    first_index,
+                    base_vertex: allocation.vertices.start as i32,
+                    first_instance: allocation.slot,
+                });
+            }
+        }
+
+        if self.draws.is_empty() {
+            return chunks;
+        }
+
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertices.buffer.slice(..));
+        render_pass.set_index_buffer(self.indices.buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+        match mode {
+            ChunkDrawMode::MultiDrawIndirect => {
+                let size = std::mem::size_of_val(self.draws.as_slice()) as u64;
+                if size > self.indirect.size() {
+                    self.indirect = Self::create_indirect(
+                        device,
+                        (self.draws.len() as u64).next_power_of_two(),
+                    );
+                }
+
+                // Written before the frame's commands run
+                queue.write_buffer(&self.indirect, 0, bytemuck::cast_slice(&self.draws));
+                render_pass.multi_draw_indexed_indirect(&self.indirect, 0, self.draws.len() as u32);
+            }
+            ChunkDrawMode::Loop => {
+                for draw in self.draws.iter() {
+                    render_pass.draw_indexed(
+                        draw.first_index..draw.first_index + draw.index_count,
+                        draw.base_vertex,
+                        draw.first_instance..draw.first_instance + 1,
+                    );
+                }
+            }
+        }
+
+        chunks
+    }
+}
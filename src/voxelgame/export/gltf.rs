@@ -0,0 +1,197 @@
+use std::path::Path;
+
+use super::{atlas_uv, chunk_name, chunk_translation, write_file, ChunkMesh, ExportResult};
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const NEAREST: u32 = 9728;
+const CLAMP_TO_EDGE: u32 = 33071;
+
+/// JSON parts and binary buffer of a glTF file being built
+#[derive(Default)]
+struct Builder {
+    bin: Vec<u8>,
+    buffer_views: Vec<String>,
+    accessors: Vec<String>,
+}
+
+impl Builder {
+    /// Appends `bytes` to the buffer, returns the index of its view
+    fn view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        // Every accessor component is 4 bytes wide
+        self.bin.resize(self.bin.len().next_multiple_of(4), 0);
+
+        let target = target
+            .map(|target| format!(r#","target":{target}"#))
+            .unwrap_or_default();
+
+        self.buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{}{target}}}"#,
+            self.bin.len(),
+            bytes.len()
+        ));
+        self.bin.extend_from_slice(bytes);
+
+        self.buffer_views.len() - 1
+    }
+
+    /// Adds an accessor over a new view of `values`, returns its index
+    fn accessor<const N: usize>(&mut self, values: &[[f32; N]], bounds: bool) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let view = self.view(&bytes, Some(ARRAY_BUFFER));
+
+        let kind = match N {
+            2 => "VEC2",
+            3 => "VEC3",
+            _ => unreachable!("Unsupported accessor width {N}"),
+        };
+
+        // Required for positions
+        let bounds = if bounds && !values.is_empty() {
+            let mut min = [f32::MAX; N];
+            let mut max = [f32::MIN; N];
+
+            for value in values {
+                for i in 0..N {
+                    min[i] = min[i].min(value[i]);
+                    max[i] = max[i].max(value[i]);
+                }
+            }
+
+            format!(
+                r#","min":{},"max":{}"#,
+                json_floats(&min),
+                json_floats(&max)
+            )
+        } else {
+            String::new()
+        };
+
+        self.accessors.push(format!(
+            r#"{{"bufferView":{view},"componentType":{FLOAT},"count":{},"type":"{kind}"{bounds}}}"#,
+            values.len()
+        ));
+
+        self.accessors.len() - 1
+    }
+
+    fn index_accessor(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let view = self.view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+
+        self.accessors.push(format!(
+            r#"{{"bufferView":{view},"componentType":{UNSIGNED_INT},"count":{},"type":"SCALAR"}}"#,
+            indices.len()
+        ));
+
+        self.accessors.len() - 1
+    }
+}
+
+fn json_floats(values: &[f32]) -> String {
+    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+
+    format!("[{}]", values.join(","))
+}
+
+/// `"key":[...]`, or nothing if `items` is empty since glTF forbids empty arrays
+fn json_array(key: &str, items: &[String]) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+
+    format!(r#","{key}":[{}]"#, items.join(","))
+}
+
+/// Writes the meshes as binary glTF with the atlas embedded.
+///
+/// Each chunk gets its own mesh and a node translated to the chunk's corner.
+pub fn write_glb(meshes: &[ChunkMesh], atlas_png: &[u8]) -> Vec<u8> {
+    let mut builder = Builder::default();
+    let mut gltf_meshes = Vec::new();
+    let mut nodes = Vec::new();
+
+    for (coord, mesh) in meshes {
+        let positions: Vec<[f32; 3]> = mesh.vertices.iter().map(|v| v.position).collect();
+        let normals: Vec<[f32; 3]> = mesh.vertices.iter().map(|v| v.normal).collect();
+        let uvs: Vec<[f32; 2]> = mesh.vertices.iter().map(atlas_uv).collect();
+
+        let position = builder.accessor(&positions, true);
+        let normal = builder.accessor(&normals, false);
+        let uv = builder.accessor(&uvs, false);
+        let indices = builder.index_accessor(&mesh.indices);
+
+        let name = chunk_name(*coord);
+
+        gltf_meshes.push(format!(
+            r#"{{"name":"{name}","primitives":[{{"attributes":{{"POSITION":{position},"NORMAL":{normal},"TEXCOORD_0":{uv}}},"indices":{indices},"material":0}}]}}"#
+        ));
+        nodes.push(format!(
+            r#"{{"name":"{name}","mesh":{},"translation":{}}}"#,
+            gltf_meshes.len() - 1,
+            json_floats(&chunk_translation(*coord))
+        ));
+    }
+
+    let image = builder.view(atlas_png, None);
+    builder.bin.resize(builder.bin.len().next_multiple_of(4), 0);
+
+    let scene_nodes: Vec<String> = (0..nodes.len()).map(|i| i.to_string()).collect();
+
+    let json = format!(
+        concat!(
+            r#"{{"asset":{{"version":"2.0","generator":"rust-concurrency"}}"#,
+            r#","scene":0,"scenes":[{{"name":"world"{scene_nodes}}}]"#,
+            r#"{nodes}{meshes}{accessors}{views}"#,
+            r#","buffers":[{{"byteLength":{bin_len}}}]"#,
+            r#","images":[{{"bufferView":{image},"mimeType":"image/png"}}]"#,
+            r#","samplers":[{{"magFilter":{nearest},"minFilter":{nearest},"wrapS":{clamp},"wrapT":{clamp}}}]"#,
+            r#","textures":[{{"source":0,"sampler":0}}]"#,
+            r#","materials":[{{"name":"terrain","pbrMetallicRoughness":{{"baseColorTexture":{{"index":0}},"metallicFactor":0,"roughnessFactor":1}}}}]"#,
+            "}}"
+        ),
+        scene_nodes = json_array("nodes", &scene_nodes),
+        nodes = json_array("nodes", &nodes),
+        meshes = json_array("meshes", &gltf_meshes),
+        accessors = json_array("accessors", &builder.accessors),
+        views = json_array("bufferViews", &builder.buffer_views),
+        bin_len = builder.bin.len(),
+        image = image,
+        nearest = NEAREST,
+        clamp = CLAMP_TO_EDGE,
+    );
+
+    let mut json = json.into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+
+    let total = 12 + 8 + json.len() + 8 + builder.bin.len();
+    let mut glb = Vec::with_capacity(total);
+
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&(total as u32).to_le_bytes());
+
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+
+    glb.extend_from_slice(&(builder.bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"BIN\0");
+    glb.extend_from_slice(&builder.bin);
+
+    glb
+}
+
+pub fn save_glb(
+    path: impl AsRef<Path>,
+    meshes: &[ChunkMesh],
+    atlas_png: &[u8],
+) -> ExportResult<()> {
+    write_file(path.as_ref(), &write_glb(meshes, atlas_png))
+}
@@ -0,0 +1,62 @@
+//! Writes world meshes to files other tools can open
+//!
+//! Exporters take the meshes of `World::chunk_meshes`, whose vertices are
+//! relative to their chunk, and the PNG of the block textures packed by
+//! `AtlasLayout::pack`. Vertex UVs are remapped into it with `atlas_uv`.
+
+pub mod gltf;
+pub mod obj;
+
+use std::fmt::Display;
+
+use super::{
+    atlas,
+    generator::chunk::{ChunkCoord, WorldCoord},
+    mesh::{MeshInfo, Vertex3d},
+};
+
+#[derive(Debug, Clone)]
+pub struct ExportError {
+    pub message: String,
+}
+
+impl ExportError {
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ExportError {}
+type ExportResult<T> = Result<T, ExportError>;
+
+/// Mesh of a single chunk, vertices relative to the chunk's corner
+pub type ChunkMesh = (ChunkCoord, MeshInfo<Vertex3d>);
+
+/// File name the atlas is written to next to exported files
+pub const ATLAS_FILE: &str = "textureatlas.png";
+
+/// UV of `vertex` in the packed atlas
+fn atlas_uv(vertex: &Vertex3d) -> [f32; 2] {
+    atlas::layout().atlas_uv(vertex.layer, vertex.uv)
+}
+
+fn chunk_name(coord: ChunkCoord) -> String {
+    format!("chunk_{}_{}_{}", coord.x, coord.y, coord.z)
+}
+
+fn chunk_translation(coord: ChunkCoord) -> [f32; 3] {
+    let corner: WorldCoord = coord.into();
+
+    [corner.x as f32, corner.y as f32, corner.z as f32]
+}
+
+fn write_file(path: &std::path::Path, contents: &[u8]) -> ExportResult<()> {
+    std::fs::write(path, contents)
+        .map_err(|e| ExportError::new(format!("Failed to write {}: {e}", path.display())))
+}
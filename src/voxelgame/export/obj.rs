@@ -0,0 +1,83 @@
+use std::{fmt::Write, path::Path};
+
+use super::{
+    atlas_uv, chunk_name, chunk_translation, write_file, ChunkMesh, ExportResult, ATLAS_FILE,
+};
+
+/// Material every face uses
+const MATERIAL: &str = "terrain";
+
+/// Writes the meshes as Wavefront OBJ with one object per chunk.
+///
+/// OBJ has no transforms, so vertices are moved to world space.
+pub fn write_obj(meshes: &[ChunkMesh], mtl_file: &str) -> String {
+    let mut obj = String::new();
+    writeln!(obj, "mtllib {mtl_file}").unwrap();
+
+    // OBJ indices are 1 based and count across the whole file
+    let mut first_index = 1;
+
+    for (coord, mesh) in meshes {
+        let [tx, ty, tz] = chunk_translation(*coord);
+
+        writeln!(obj, "o {}", chunk_name(*coord)).unwrap();
+        writeln!(obj, "usemtl {MATERIAL}").unwrap();
+
+        for vertex in mesh.vertices.iter() {
+            let [x, y, z] = vertex.position;
+            writeln!(obj, "v {} {} {}", x + tx, y + ty, z + tz).unwrap();
+        }
+
+        // OBJ puts V = 0 at the bottom of the texture
+        for vertex in mesh.vertices.iter() {
+            let [u, v] = atlas_uv(vertex);
+            writeln!(obj, "vt {} {}", u, 1.0 - v).unwrap();
+        }
+
+        for vertex in mesh.vertices.iter() {
+            let [x, y, z] = vertex.normal;
+            writeln!(obj, "vn {x} {y} {z}").unwrap();
+        }
+
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize + first_index);
+            writeln!(obj, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}").unwrap();
+        }
+
+        first_index += mesh.vertices.len();
+    }
+
+    obj
+}
+
+/// Material library with the texture atlas as the diffuse map
+pub fn write_mtl(atlas_file: &str) -> String {
+    format!(
+        "newmtl {MATERIAL}\n\
+         Ka 1 1 1\n\
+         Kd 1 1 1\n\
+         Ks 0 0 0\n\
+         d 1\n\
+         illum 1\n\
+         map_Kd {atlas_file}\n\
+         map_d {atlas_file}\n"
+    )
+}
+
+/// Writes `<name>.obj`, `<name>.mtl` and the texture atlas into `directory`
+pub fn save_obj(
+    directory: impl AsRef<Path>,
+    name: &str,
+    meshes: &[ChunkMesh],
+    atlas_png: &[u8],
+) -> ExportResult<()> {
+    let directory = directory.as_ref();
+    let mtl_file = format!("{name}.mtl");
+
+    write_file(
+        &directory.join(format!("{name}.obj")),
+        write_obj(meshes, &mtl_file).as_bytes(),
+    )?;
+    write_file(&directory.join(&mtl_file), write_mtl(ATLAS_FILE).as_bytes())?;
+    write_file(&directory.join(ATLAS_FILE), atlas_png)
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    f32::consts::{PI, TAU},
+    sync::{Arc, Mutex},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    chunk::{Chunk, ChunkLocalCoord, WorldCoord, CHUNK_SIZE},
+    config::CarverConfig,
+    feature::hash_cell,
+    voxel::Blocks,
+};
+
+/// Side of the square regions tunnels are traced in, in blocks
+pub const REGION_SIZE: i32 = 8 * CHUNK_SIZE as i32;
+
+/// Regions kept before the cache is cleared
+const CACHE_LIMIT: usize = 256;
+
+type RegionCache = HashMap<(i32, i32), Arc<Vec<Carve>>>;
+
+/// One step of a tunnel, everything inside the ellipsoid is carved out
+#[derive(Clone, Copy, Debug)]
+pub struct Carve {
+    pub center: [f32; 3],
+    pub radius: f32,
+    pub vertical_radius: f32,
+}
+
+impl Carve {
+    fn contains(&self, x: f32, y: f32, z: f32) -> bool {
+        let dx = (x - self.center[0]) / self.radius;
+        let dy = (y - self.center[1]) / self.vertical_radius;
+        let dz = (z - self.center[2]) / self.radius;
+
+        dx * dx + dy * dy + dz * dz <= 1.0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CarverKind {
+    /// Winding round tunnels
+    Worm,
+    /// Long narrow and tall cracks that mostly stay level
+    Ravine,
+}
+
+/// Carves tunnels traced from seeded starting points.
+///
+/// Tunnels are traced for a whole region at a time and cached, so that every
+/// chunk a tunnel passes through can carve its own part independently.
+/// Tunnels never go further than one region from where they start.
+pub struct Carver {
+    kind: CarverKind,
+    config: CarverConfig,
+    seed: i32,
+    regions: Mutex<RegionCache>,
+}
+
+#[allow(dead_code)]
+impl Carver {
+    pub fn new(kind: CarverKind, config: CarverConfig, seed: i32) -> Self {
+        Self {
+            kind,
+            config,
+            seed,
+            regions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn kind(&self) -> CarverKind {
+        self.kind
+    }
+
+    /// Every step of the tunnels starting in the region
+    pub fn region(&self, region_x: i32, region_z: i32) -> Arc<Vec<Carve>> {
+        if let Some(carves) = self.regions.lock().unwrap().get(&(region_x, region_z)) {
+            return carves.clone();
+        }
+
+        // Traced without holding the lock, a region traced twice is identical
+        let carves = Arc::new(self.trace_region(region_x, region_z));
+
+        let mut regions = self.regions.lock().unwrap();
+        if regions.len() >= CACHE_LIMIT {
+            regions.clear();
+        }
+        regions.insert((region_x, region_z), carves.clone());
+
+        carves
+    }
+
+    fn trace_region(&self, region_x: i32, region_z: i32) -> Vec<Carve> {
+        let salt = match self.kind {
+            CarverKind::Worm => 0x5752_4f4d,
+            CarverKind::Ravine => 0x5241_5649,
+        };
+        let mut rng = StdRng::seed_from_u64(hash_cell(self.seed, salt, region_x, region_z));
+
+        // Whole tunnels for the integer part, one more by chance for the rest
+        let frequency = self.config.frequency.max(0.0);
+        let mut count = frequency as u32;
+        if rng.random::<f32>() < frequency.fract() {
+            count += 1;
+        }
+
+        let mut carves = Vec::new();
+        for _ in 0..count {
+            let start = [
+                (region_x * REGION_SIZE) as f32 + rng.random_range(0.0..REGION_SIZE as f32),
+                rng.random_range(
+                    self.config.min_y as f32..=self.config.max_y.max(self.config.min_y) as f32,
+                ),
+                (region_z * REGION_SIZE) as f32 + rng.random_range(0.0..REGION_SIZE as f32),
+            ];
+
+            self.trace(&mut rng, start, &mut carves);
+        }
+
+        carves
+    }
+
+    fn trace(&self, rng: &mut StdRng, start: [f32; 3], carves: &mut Vec<Carve>) {
+        let config = &self.config;
+
+        // Kept short enough to stay within the neighbouring regions
+        let length = config
+            .length
+            .min((REGION_SIZE as u32).saturating_sub(config.max_radius.ceil() as u32));
+        let width = rng.random_range(config.min_radius..=config.max_radius.max(config.min_radius));
+
+        let (max_pitch, turn): (f32, f32) = match self.kind {
+            CarverKind::Worm => (0.8, 0.2),
+            CarverKind::Ravine => (0.1, 0.05),
+        };
+
+        let mut position = start;
+        let mut yaw = rng.random_range(0.0..TAU);
+        let mut pitch = rng.random_range(-max_pitch..=max_pitch) * 0.5;
+        let (mut yaw_change, mut pitch_change) = (0.0, 0.0);
+
+        for step in 0..length {
+            // Thinner at both ends
+            let t = step as f32 / length.max(1) as f32;
+            let radius = width * (0.5 + 0.5 * (t * PI).sin());
+
+            carves.push(Carve {
+                center: position,
+                radius,
+                vertical_radius: radius * config.vertical_scale,
+            });
+
+            position[0] += yaw.cos() * pitch.cos();
+            position[1] += pitch.sin();
+            position[2] += yaw.sin() * pitch.cos();
+
+            // Turning gradually keeps the tunnel smooth
+            yaw_change = yaw_change * 0.8 + rng.random_range(-turn..=turn);
+            pitch_change = pitch_change * 0.8 + rng.random_range(-turn..=turn) * 0.5;
+            yaw += yaw_change * 0.5;
+            pitch = (pitch + pitch_change * 0.5).clamp(-max_pitch, max_pitch);
+        }
+    }
+
+    /// Replaces the blocks of `chunk` inside any tunnel with air
+    pub fn carve(&self, chunk: &mut Chunk) {
+        let chunk_min: WorldCoord = chunk.coord.into();
+        let size = CHUNK_SIZE as i32;
+
+        let regions_x = (chunk_min.x.div_euclid(REGION_SIZE) - 1)
+            ..=((chunk_min.x + size).div_euclid(REGION_SIZE) + 1);
+        let regions_z = (chunk_min.z.div_euclid(REGION_SIZE) - 1)
+            ..=((chunk_min.z + size).div_euclid(REGION_SIZE) + 1);
+
+        let (min, max) = (
+            [chunk_min.x as f32, chunk_min.y as f32, chunk_min.z as f32],
+            [
+                (chunk_min.x + size) as f32,
+                (chunk_min.y + size) as f32,
+                (chunk_min.z + size) as f32,
+            ],
+        );
+
+        for region_x in regions_x {
+            for region_z in regions_z.clone() {
+                for carve in self.region(region_x, region_z).iter() {
+                    let reach = [carve.radius, carve.vertical_radius, carve.radius];
+
+                    if (0..3).any(|i| {
+                        carve.center[i] + reach[i] < min[i] || carve.center[i] - reach[i] > max[i]
+                    }) {
+                        continue;
+                    }
+
+                    Self::carve_one(chunk, chunk_min, carve, reach);
+                }
+            }
+        }
+    }
+
+    fn carve_one(chunk: &mut Chunk, chunk_min: WorldCoord, carve: &Carve, reach: [f32; 3]) {
+        let min = [chunk_min.x, chunk_min.y, chunk_min.z];
+
+        // Local range of blocks the ellipsoid can touch on each axis
+        let range = |axis: usize| {
+            let low = (carve.center[axis] - reach[axis]).floor() as i32 - min[axis];
+            let high = (carve.center[axis] + reach[axis]).ceil() as i32 - min[axis];
+
+            low.max(0) as usize..(high + 1).clamp(0, CHUNK_SIZE as i32) as usize
+        };
+
+        for x in range(0) {
+            for y in range(1) {
+                for z in range(2) {
+                    // Block centers
+                    let inside = carve.contains(
+                        (min[0] + x as i32) as f32 + 0.5,
+                        (min[1] + y as i32) as f32 + 0.5,
+                        (min[2] + z as i32) as f32 + 0.5,
+                    );
+
+                    if inside {
+                        chunk.set_voxel(ChunkLocalCoord { x, y, z }, Blocks::AIR.default_state());
+                    }
+                }
+            }
+        }
+    }
+}
@@ -171,6 +171,18 @@ impl BlockOffsetCoord {
     }
 }
 
+impl Neg for BlockOffsetCoord {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
 impl Into<ChunkLocalCoord> for BlockOffsetCoord {
     fn into(self) -> ChunkLocalCoord {
         ChunkLocalCoord {
@@ -181,6 +193,12 @@ impl Into<ChunkLocalCoord> for BlockOffsetCoord {
     }
 }
 
+impl From<BlockOffsetCoord> for cgmath::Vector3<f32> {
+    fn from(value: BlockOffsetCoord) -> Self {
+        cgmath::Vector3::new(value.x as f32, value.y as f32, value.z as f32)
+    }
+}
+
 impl From<cgmath::Vector3<f32>> for BlockOffsetCoord {
     fn from(value: cgmath::Vector3<f32>) -> Self {
         Self {
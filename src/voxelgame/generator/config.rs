@@ -0,0 +1,308 @@
+use std::{fmt::Display, path::Path};
+
+use fastnoise_lite::FastNoiseLite;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone)]
+pub struct ConfigError {
+    pub message: String,
+}
+
+impl ConfigError {
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+type ConfigResult<T> = Result<T, ConfigError>;
+
+/// Mirrors `fastnoise_lite::NoiseType`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseType {
+    OpenSimplex2,
+    OpenSimplex2S,
+    Cellular,
+    Perlin,
+    ValueCubic,
+    Value,
+}
+
+impl From<NoiseType> for fastnoise_lite::NoiseType {
+    fn from(value: NoiseType) -> Self {
+        match value {
+            NoiseType::OpenSimplex2 => Self::OpenSimplex2,
+            NoiseType::OpenSimplex2S => Self::OpenSimplex2S,
+            NoiseType::Cellular => Self::Cellular,
+            NoiseType::Perlin => Self::Perlin,
+            NoiseType::ValueCubic => Self::ValueCubic,
+            NoiseType::Value => Self::Value,
+        }
+    }
+}
+
+/// Mirrors the non domain warp variants of `fastnoise_lite::FractalType`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FractalType {
+    None,
+    Fbm,
+    Ridged,
+    PingPong,
+}
+
+impl From<FractalType> for fastnoise_lite::FractalType {
+    fn from(value: FractalType) -> Self {
+        match value {
+            FractalType::None => Self::None,
+            FractalType::Fbm => Self::FBm,
+            FractalType::Ridged => Self::Ridged,
+            FractalType::PingPong => Self::PingPong,
+        }
+    }
+}
+
+/// Mirrors `fastnoise_lite::DomainWarpType`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DomainWarpType {
+    OpenSimplex2,
+    OpenSimplex2Reduced,
+    BasicGrid,
+}
+
+impl From<DomainWarpType> for fastnoise_lite::DomainWarpType {
+    fn from(value: DomainWarpType) -> Self {
+        match value {
+            DomainWarpType::OpenSimplex2 => Self::OpenSimplex2,
+            DomainWarpType::OpenSimplex2Reduced => Self::OpenSimplex2Reduced,
+            DomainWarpType::BasicGrid => Self::BasicGrid,
+        }
+    }
+}
+
+/// Offsets sample positions by another noise before sampling
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DomainWarpConfig {
+    pub warp_type: DomainWarpType,
+    /// Largest offset in blocks
+    pub amplitude: f32,
+    pub frequency: f32,
+}
+
+impl Default for DomainWarpConfig {
+    fn default() -> Self {
+        Self {
+            warp_type: DomainWarpType::OpenSimplex2,
+            amplitude: 30.0,
+            frequency: 0.005,
+        }
+    }
+}
+
+impl DomainWarpConfig {
+    pub fn build(&self, seed: i32) -> FastNoiseLite {
+        let mut warp = FastNoiseLite::with_seed(seed);
+        warp.set_domain_warp_type(Some(self.warp_type.into()));
+        warp.set_domain_warp_amp(Some(self.amplitude));
+        warp.set_frequency(Some(self.frequency));
+        warp
+    }
+}
+
+/// Settings of the noise every terrain feature is sampled from
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NoiseConfig {
+    pub noise_type: NoiseType,
+    pub frequency: f32,
+    pub fractal_type: FractalType,
+    pub octaves: u32,
+    /// Frequency multiplier between octaves
+    pub lacunarity: f32,
+    /// Amplitude multiplier between octaves
+    pub gain: f32,
+    pub domain_warp: Option<DomainWarpConfig>,
+}
+
+impl Default for NoiseConfig {
+    fn default() -> Self {
+        Self {
+            noise_type: NoiseType::OpenSimplex2,
+            frequency: 0.01,
+            fractal_type: FractalType::Fbm,
+            octaves: 4,
+            lacunarity: 2.0,
+            gain: 0.5,
+            domain_warp: None,
+        }
+    }
+}
+
+impl NoiseConfig {
+    /// Noise and optional domain warp set up with these settings
+    pub fn build(&self, seed: i32) -> (FastNoiseLite, Option<FastNoiseLite>) {
+        let mut noise = FastNoiseLite::with_seed(seed);
+        noise.set_noise_type(Some(self.noise_type.into()));
+        noise.set_frequency(Some(self.frequency));
+        noise.set_fractal_type(Some(self.fractal_type.into()));
+        noise.set_fractal_octaves(Some(self.octaves.max(1) as i32));
+        noise.set_fractal_lacunarity(Some(self.lacunarity));
+        noise.set_fractal_gain(Some(self.gain));
+
+        let warp = self
+            .domain_warp
+            .as_ref()
+            .map(|config| config.build(seed.wrapping_add(1)));
+
+        (noise, warp)
+    }
+}
+
+/// Terrain from 3d noise pulled towards the height noise, allowing cliffs,
+/// overhangs and floating islands
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DensityConfig {
+    /// How strongly blocks are pulled towards the height noise,
+    /// lower values give more overhangs and floating islands
+    pub squash_factor: f32,
+    /// Scale of the 3d noise
+    pub scale: f32,
+    /// Warps only the 3d noise, on top of `NoiseConfig::domain_warp`
+    pub domain_warp: Option<DomainWarpConfig>,
+}
+
+impl Default for DensityConfig {
+    fn default() -> Self {
+        Self {
+            squash_factor: 3.0,
+            scale: 0.6,
+            domain_warp: Some(DomainWarpConfig {
+                amplitude: 20.0,
+                frequency: 0.01,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+/// Tunnels carved through the terrain after it's generated.
+/// Every field has to be given, a frequency of 0 disables the carver.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CarverConfig {
+    /// Average tunnels starting in each region, see `carver::REGION_SIZE`
+    pub frequency: f32,
+    pub min_radius: f32,
+    pub max_radius: f32,
+    /// Vertical radius relative to the horizontal one
+    pub vertical_scale: f32,
+    /// Length in blocks, at most about a region
+    pub length: u32,
+    /// Heights tunnels start between
+    pub min_y: i32,
+    pub max_y: i32,
+}
+
+/// Everything `NoiseGenerator` can be tuned with, scales are relative to the noise frequency
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NoiseGeneratorConfig {
+    /// Horizontal scale of the terrain height
+    pub scale: f32,
+    /// Average terrain height
+    pub base_height: f32,
+    /// Furthest the terrain goes above or below `base_height`
+    pub height_amplitude: f32,
+    /// Depth of dirt under the surface
+    pub dirt_depth: u32,
+    pub sand_scale: f32,
+    /// Sand replaces grass where the sand noise is below this
+    pub sand_threshold: f32,
+    pub cave_scale: f32,
+    /// Scale of the noise deciding how cave-heavy an area is
+    pub caviness_scale: f32,
+    /// Caves are carved where the cave noise is below this, lower means fewer caves
+    pub cave_threshold: f32,
+    pub forest_scale: f32,
+    /// Forests grow where the forest noise is above this
+    pub forest_threshold: f32,
+    /// Multiplies how often trees and other plants grow, 0 disables them
+    pub vegetation_density: f32,
+    pub noise: NoiseConfig,
+    /// Uses 3d density instead of a heightfield when set
+    pub density: Option<DensityConfig>,
+    pub worms: CarverConfig,
+    pub ravines: CarverConfig,
+}
+
+impl Default for NoiseGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            scale: 0.3,
+            base_height: 40.0,
+            height_amplitude: 90.0,
+            dirt_depth: 2,
+            sand_scale: 1.8,
+            sand_threshold: -0.5,
+            cave_scale: 1.2,
+            caviness_scale: 0.09,
+            cave_threshold: -0.23,
+            forest_scale: 0.5,
+            forest_threshold: 0.1,
+            vegetation_density: 1.0,
+            noise: NoiseConfig::default(),
+            density: None,
+            worms: CarverConfig {
+                frequency: 6.0,
+                min_radius: 1.5,
+                max_radius: 3.5,
+                vertical_scale: 0.8,
+                length: 160,
+                min_y: -80,
+                max_y: 30,
+            },
+            ravines: CarverConfig {
+                frequency: 0.3,
+                min_radius: 2.0,
+                max_radius: 4.0,
+                vertical_scale: 5.0,
+                length: 120,
+                min_y: 0,
+                max_y: 30,
+            },
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl NoiseGeneratorConfig {
+    pub fn from_toml(text: &str) -> ConfigResult<Self> {
+        toml::from_str(text).map_err(|e| ConfigError::new(format!("Invalid config: {e}")))
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).expect("Config is always serializable")
+    }
+
+    /// Reads the config at `path`, falling back to the defaults if there is no such file
+    pub fn load_or_default(path: impl AsRef<Path>) -> ConfigResult<Self> {
+        match std::fs::read_to_string(path.as_ref()) {
+            Ok(text) => Self::from_toml(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(ConfigError::new(format!(
+                "Failed to read {}: {e}",
+                path.as_ref().display()
+            ))),
+        }
+    }
+}
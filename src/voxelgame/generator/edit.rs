@@ -0,0 +1,139 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    chunk::{BlockOffsetCoord, ChunkCoord, ChunkLocalCoord, WorldCoord},
+    voxel::Voxel,
+    World,
+};
+
+/// Batch of voxel writes applied to the world at once.
+///
+/// Writing the same coordinate twice keeps the last value.
+#[derive(Clone, Debug, Default)]
+pub struct WorldEdit {
+    changes: HashMap<WorldCoord, Voxel>,
+}
+
+#[allow(dead_code)]
+impl WorldEdit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Edit filling a sphere around `center` with `voxel`
+    pub fn sphere(center: WorldCoord, radius: u32, voxel: Voxel) -> Self {
+        let mut edit = Self::new();
+        let radius = radius as i32;
+
+        for i in -radius..radius {
+            for j in -radius..radius {
+                for k in -radius..radius {
+                    if i * i + j * j + k * k > radius * radius {
+                        continue;
+                    }
+
+                    edit.set(center + BlockOffsetCoord { x: i, y: j, z: k }, voxel);
+                }
+            }
+        }
+
+        edit
+    }
+
+    pub fn set(&mut self, coord: WorldCoord, voxel: Voxel) {
+        self.changes.insert(coord, voxel);
+    }
+
+    pub fn get(&self, coord: WorldCoord) -> Option<Voxel> {
+        self.changes.get(&coord).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Approximate amount of memory taken by the edit in bytes
+    pub fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.changes.capacity() * std::mem::size_of::<(WorldCoord, Voxel)>()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (WorldCoord, Voxel)> + '_ {
+        self.changes.iter().map(|(c, v)| (*c, *v))
+    }
+}
+
+#[allow(dead_code)]
+impl<T> World<T> {
+    /// Applies all writes of `edit` under a single lock and schedules every affected
+    /// chunk for remeshing once.
+    ///
+    /// Writes into chunks that aren't loaded are dropped.
+    /// Returns an edit that restores the previous values when applied.
+    pub fn apply_edit(&mut self, edit: &WorldEdit) -> WorldEdit {
+        let mut previous = WorldEdit::new();
+        let mut chunks_affected: HashSet<ChunkCoord> = HashSet::new();
+
+        let mut lock = self.chunks.lock().unwrap();
+
+        for (world_coord, voxel) in edit.iter() {
+            let chunk_coord: ChunkCoord = world_coord.into();
+            let local_coord: ChunkLocalCoord = world_coord.into();
+
+            let Some(chunk) = lock.get_mut(&chunk_coord) else {
+                continue;
+            };
+
+            let Some(old) = chunk.get_voxel(local_coord) else {
+                continue;
+            };
+
+            previous.set(world_coord, old);
+
+            if old == voxel {
+                continue;
+            }
+
+            chunk.set_voxel(local_coord, voxel);
+            chunks_affected.insert(chunk_coord);
+
+            // Faces of neighbouring chunks touching the voxel might have changed too
+            let neighbours = [
+                (local_coord.left(), chunk_coord.left()),
+                (local_coord.right(), chunk_coord.right()),
+                (local_coord.up(), chunk_coord.up()),
+                (local_coord.down(), chunk_coord.down()),
+                (local_coord.front(), chunk_coord.front()),
+                (local_coord.back(), chunk_coord.back()),
+            ];
+
+            for (inside, neighbour) in neighbours {
+                if inside.is_none() && lock.contains_key(&neighbour) {
+                    chunks_affected.insert(neighbour);
+                }
+            }
+        }
+
+        drop(lock);
+
+        if !chunks_affected.is_empty() {
+            self.revision += 1;
+        }
+
+        let mut lock = self.meshgen_queue.lock().unwrap();
+        for coord in chunks_affected.iter() {
+            if !lock.contains(coord) {
+                lock.push_front(*coord);
+            }
+        }
+
+        let mut lock = self.meshed_chunks.lock().unwrap();
+        chunks_affected.iter().for_each(|c| _ = lock.remove(c));
+
+        previous
+    }
+}
@@ -0,0 +1,169 @@
+use super::{
+    chunk::{BlockOffsetCoord, Chunk, ChunkLocalCoord, WorldCoord, CHUNK_SIZE},
+    structure::Structure,
+    voxel::{Blocks, Voxel},
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Biome {
+    Plains,
+    Forest,
+    Desert,
+}
+
+/// What features get to know about the terrain they're placed on.
+///
+/// Answers come from the generator's noise rather than from generated chunks,
+/// so they are the same no matter which chunk asks.
+pub trait Terrain {
+    /// Height of the topmost terrain block at world X and Z
+    fn surface_height(&self, x: i32, z: i32) -> i32;
+
+    /// Block at `surface_height`
+    fn surface_block(&self, _x: i32, _z: i32) -> Voxel {
+        Blocks::GRASS_BLOCK.default_state()
+    }
+
+    fn biome(&self, _x: i32, _z: i32) -> Biome {
+        Biome::Plains
+    }
+}
+
+/// Grass plains with the height given by the function
+impl<F: Fn(i32, i32) -> i32> Terrain for F {
+    fn surface_height(&self, x: i32, z: i32) -> i32 {
+        self(x, z)
+    }
+}
+
+/// Something placed on top of the terrain by a generator, such as a structure.
+///
+/// Chunks are generated independently and in any order, so a feature spanning
+/// several chunks has to be able to place each part of itself on its own.
+pub trait Feature: Sync + Send {
+    /// Writes the part of the feature overlapping `chunk`.
+    fn place(&self, chunk: &mut Chunk, seed: i32, terrain: &dyn Terrain);
+}
+
+/// Deterministic 64 bit hash of a seed and a cell position
+pub fn hash_cell(seed: i32, salt: u64, x: i32, z: i32) -> u64 {
+    // SplitMix64 finalizer applied to each input in turn
+    let mix = |mut v: u64| {
+        v = (v ^ (v >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        v = (v ^ (v >> 27)).wrapping_mul(0x94d049bb133111eb);
+        v ^ (v >> 31)
+    };
+
+    let mut hash = mix(seed as u64 ^ salt);
+    hash = mix(hash ^ x as u32 as u64);
+    mix(hash.wrapping_add(0x9e3779b97f4a7c15) ^ z as u32 as u64)
+}
+
+/// Scatters copies of a structure over the terrain surface.
+///
+/// The world is split into square cells of `spacing` blocks and each cell
+/// holds at most one copy at a position derived from the seed.
+pub struct StructureFeature {
+    structure: Structure,
+    spacing: u32,
+    /// Chance of a cell holding a copy, `0.0..=1.0`
+    chance: f32,
+    /// Added to the surface height, negative values bury the structure
+    y_offset: i32,
+    /// Keeps features with the same spacing from lining up
+    salt: u64,
+}
+
+#[allow(dead_code)]
+impl StructureFeature {
+    pub fn new(structure: Structure, spacing: u32, chance: f32, salt: u64) -> Self {
+        Self {
+            structure,
+            spacing: spacing.max(1),
+            chance,
+            y_offset: 0,
+            salt,
+        }
+    }
+
+    pub fn with_y_offset(mut self, y_offset: i32) -> Self {
+        self.y_offset = y_offset;
+        self
+    }
+
+    /// Minimum corner of the copy in the cell, if it has one
+    fn origin(
+        &self,
+        seed: i32,
+        cell_x: i32,
+        cell_z: i32,
+        terrain: &dyn Terrain,
+    ) -> Option<WorldCoord> {
+        let hash = hash_cell(seed, self.salt, cell_x, cell_z);
+        let spacing = self.spacing as u64;
+
+        if (hash & 0xffff) as f32 / 65536.0 >= self.chance {
+            return None;
+        }
+
+        let x = cell_x * self.spacing as i32 + ((hash >> 16) % spacing) as i32;
+        let z = cell_z * self.spacing as i32 + ((hash >> 40) % spacing) as i32;
+
+        // Centered on the surface it stands on
+        let [sx, _, sz] = self.structure.size();
+        let y = terrain.surface_height(x + sx as i32 / 2, z + sz as i32 / 2) + 1 + self.y_offset;
+
+        Some(WorldCoord { x, y, z })
+    }
+}
+
+impl Feature for StructureFeature {
+    fn place(&self, chunk: &mut Chunk, seed: i32, terrain: &dyn Terrain) {
+        let chunk_min: WorldCoord = chunk.coord.into();
+        let chunk_size = CHUNK_SIZE as i32;
+        let spacing = self.spacing as i32;
+        let [sx, _, sz] = self.structure.size();
+
+        // Cells whose copy could reach into the chunk
+        let cells_x = (chunk_min.x - sx as i32).div_euclid(spacing)
+            ..=(chunk_min.x + chunk_size - 1).div_euclid(spacing);
+        let cells_z = (chunk_min.z - sz as i32).div_euclid(spacing)
+            ..=(chunk_min.z + chunk_size - 1).div_euclid(spacing);
+
+        for cell_x in cells_x {
+            for cell_z in cells_z.clone() {
+                let Some(origin) = self.origin(seed, cell_x, cell_z, terrain) else {
+                    continue;
+                };
+
+                for ([x, y, z], voxel) in self.structure.iter() {
+                    if voxel == Blocks::AIR.default_state() {
+                        continue;
+                    }
+
+                    let offset = origin
+                        + BlockOffsetCoord {
+                            x: x as i32,
+                            y: y as i32,
+                            z: z as i32,
+                        }
+                        - chunk_min;
+
+                    if (0..chunk_size).contains(&offset.x)
+                        && (0..chunk_size).contains(&offset.y)
+                        && (0..chunk_size).contains(&offset.z)
+                    {
+                        chunk.set_voxel(
+                            ChunkLocalCoord {
+                                x: offset.x as usize,
+                                y: offset.y as usize,
+                                z: offset.z as usize,
+                            },
+                            voxel,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
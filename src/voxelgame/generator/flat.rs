@@ -0,0 +1,180 @@
+use super::{
+    chunk::{Chunk, ChunkLocalCoord, WorldCoord, CHUNK_SIZE},
+    voxel::{Blocks, Voxel},
+    Generator,
+};
+
+/// Same column of layers everywhere, nothing above or below them
+pub struct SuperflatGenerator {
+    /// Blocks and their thickness, bottom to top
+    layers: Vec<(Voxel, u32)>,
+    /// Height of the bottom of the lowest layer
+    base: i32,
+}
+
+#[allow(dead_code)]
+impl SuperflatGenerator {
+    pub fn new(layers: Vec<(Voxel, u32)>) -> Self {
+        Self { layers, base: 0 }
+    }
+
+    pub fn with_base(mut self, base: i32) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Parses layers written bottom to top like `stone*60,dirt*3,grass_block`
+    pub fn parse_layers(layers: &str) -> Result<Vec<(Voxel, u32)>, String> {
+        layers
+            .split(',')
+            .map(|layer| {
+                let (name, count) = match layer.split_once('*') {
+                    Some((name, count)) => (
+                        name,
+                        count
+                            .trim()
+                            .parse()
+                            .map_err(|_| format!("Invalid layer thickness in \"{layer}\""))?,
+                    ),
+                    None => (layer, 1),
+                };
+
+                let block =
+                    Blocks::by_name(name).ok_or_else(|| format!("Unknown block \"{name}\""))?;
+
+                Ok((block.default_state(), count))
+            })
+            .collect()
+    }
+
+    /// Block at height `y`, air outside of the layers
+    fn block_at(&self, y: i32) -> Voxel {
+        let mut bottom = self.base;
+
+        for (voxel, thickness) in self.layers.iter() {
+            let top = bottom + *thickness as i32;
+
+            if (bottom..top).contains(&y) {
+                return *voxel;
+            }
+
+            bottom = top;
+        }
+
+        Blocks::AIR.default_state()
+    }
+}
+
+impl Default for SuperflatGenerator {
+    fn default() -> Self {
+        Self::new(vec![
+            (Blocks::STONE.default_state(), 1),
+            (Blocks::DIRT_BLOCK.default_state(), 2),
+            (Blocks::GRASS_BLOCK.default_state(), 1),
+        ])
+    }
+}
+
+impl Generator for SuperflatGenerator {
+    fn generate(&self, chunk: &mut Chunk) {
+        let chunk_min: WorldCoord = chunk.coord.into();
+
+        for y in 0..CHUNK_SIZE {
+            let voxel = self.block_at(chunk_min.y + y as i32);
+
+            if voxel == Blocks::AIR.default_state() {
+                continue;
+            }
+
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    chunk.set_voxel(ChunkLocalCoord { x, y, z }, voxel);
+                }
+            }
+        }
+    }
+}
+
+/// Every registered block laid out in a grid on a checkerboard floor,
+/// for checking textures and meshing.
+pub struct DebugGenerator {
+    /// Blocks between neighbouring blocks of the grid, at least 1
+    spacing: i32,
+}
+
+#[allow(dead_code)]
+impl DebugGenerator {
+    /// Height of the checkerboard, blocks are shown right above it
+    const FLOOR: i32 = 0;
+
+    pub fn new(spacing: u32) -> Self {
+        Self {
+            spacing: spacing.max(1) as i32,
+        }
+    }
+
+    /// Blocks per grid row, to keep the grid close to square
+    fn columns() -> i32 {
+        ((Blocks::BLOCKS.len() - 1) as f32).sqrt().ceil() as i32
+    }
+
+    /// Block shown at world `x`, `z`, if any. Air is left out.
+    pub fn block_at(&self, x: i32, z: i32) -> Option<Voxel> {
+        if x < 0 || z < 0 || x % self.spacing != 0 || z % self.spacing != 0 {
+            return None;
+        }
+
+        let (column, row) = (x / self.spacing, z / self.spacing);
+        if column >= Self::columns() {
+            return None;
+        }
+
+        Blocks::BLOCKS
+            .get((row * Self::columns() + column) as usize + 1)
+            .map(|block| block.default_state())
+    }
+}
+
+impl Default for DebugGenerator {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+impl Generator for DebugGenerator {
+    fn generate(&self, chunk: &mut Chunk) {
+        let chunk_min: WorldCoord = chunk.coord.into();
+
+        let mut set = |x: usize, y: i32, z: usize, voxel: Voxel| {
+            let y = y - chunk_min.y;
+
+            if (0..CHUNK_SIZE as i32).contains(&y) {
+                chunk.set_voxel(
+                    ChunkLocalCoord {
+                        x,
+                        y: y as usize,
+                        z,
+                    },
+                    voxel,
+                );
+            }
+        };
+
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let (wx, wz) = (chunk_min.x + x as i32, chunk_min.z + z as i32);
+
+                let checker = if (wx + wz).rem_euclid(2) == 0 {
+                    Blocks::STONE
+                } else {
+                    Blocks::SAND
+                };
+                set(x, Self::FLOOR, z, checker.default_state());
+
+                if let Some(block) = self.block_at(wx, wz) {
+                    set(x, Self::FLOOR + 1, z, block);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::{fmt::Display, path::Path};
+
+use image::{ImageBuffer, Luma, RgbImage};
+
+use super::{
+    chunk::{Chunk, ChunkLocalCoord, WorldCoord, CHUNK_SIZE},
+    voxel::{Blocks, Voxel},
+    Generator,
+};
+
+#[derive(Debug, Clone)]
+pub struct HeightmapError {
+    pub message: String,
+}
+
+impl HeightmapError {
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl Display for HeightmapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for HeightmapError {}
+type HeightmapResult<T> = Result<T, HeightmapError>;
+
+type HeightImage = ImageBuffer<Luma<u16>, Vec<u16>>;
+
+/// Builds terrain from a grayscale image, black being the lowest point
+/// and white the highest.
+///
+/// Columns outside of the image are left empty.
+pub struct HeightmapGenerator {
+    heights: HeightImage,
+    /// Surface blocks are picked by nearest color when set
+    colors: Option<RgbImage>,
+    /// Blocks per pixel
+    horizontal_scale: f32,
+    /// Height of white above black in blocks
+    vertical_scale: f32,
+    /// Where the top left pixel at black ends up
+    origin: WorldCoord,
+}
+
+#[allow(dead_code)]
+impl HeightmapGenerator {
+    /// Depth of dirt under grass
+    const SOIL_DEPTH: i32 = 3;
+
+    pub fn new(heights: HeightImage) -> Self {
+        Self {
+            heights,
+            colors: None,
+            horizontal_scale: 1.0,
+            vertical_scale: 128.0,
+            origin: WorldCoord { x: 0, y: 0, z: 0 },
+        }
+    }
+
+    /// Loads a heightmap from any image format `image` supports,
+    /// color images are converted to grayscale.
+    pub fn from_file(path: impl AsRef<Path>) -> HeightmapResult<Self> {
+        let image = image::open(path.as_ref()).map_err(|e| {
+            HeightmapError::new(format!(
+                "Failed to load heightmap {}: {e}",
+                path.as_ref().display()
+            ))
+        })?;
+
+        Ok(Self::new(image.into_luma16()))
+    }
+
+    pub fn with_scale(mut self, horizontal: f32, vertical: f32) -> Self {
+        self.horizontal_scale = horizontal.max(f32::EPSILON);
+        self.vertical_scale = vertical;
+        self
+    }
+
+    pub fn with_origin(mut self, origin: WorldCoord) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Picks surface blocks from a color image stretched over the heightmap
+    pub fn with_colors(mut self, colors: RgbImage) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+
+    pub fn with_color_file(self, path: impl AsRef<Path>) -> HeightmapResult<Self> {
+        let image = image::open(path.as_ref()).map_err(|e| {
+            HeightmapError::new(format!(
+                "Failed to load color map {}: {e}",
+                path.as_ref().display()
+            ))
+        })?;
+
+        Ok(self.with_colors(image.into_rgb8()))
+    }
+
+    /// Position in pixels of the center of the world column at `x`, `z`
+    fn pixel(&self, x: i32, z: i32) -> (f32, f32) {
+        (
+            ((x - self.origin.x) as f32 + 0.5) / self.horizontal_scale,
+            ((z - self.origin.z) as f32 + 0.5) / self.horizontal_scale,
+        )
+    }
+
+    /// Terrain height at world `x`, `z`, interpolated between pixels.
+    /// `None` outside of the image.
+    pub fn height(&self, x: i32, z: i32) -> Option<i32> {
+        let (px, pz) = self.pixel(x, z);
+        let (width, height) = self.heights.dimensions();
+
+        if px < 0.0 || pz < 0.0 || px >= width as f32 || pz >= height as f32 {
+            return None;
+        }
+
+        let sample = |x: u32, z: u32| {
+            self.heights
+                .get_pixel(x.min(width - 1), z.min(height - 1))
+                .0[0] as f32
+                / u16::MAX as f32
+        };
+
+        // Pixel centers sit at half coordinates
+        let (fx, fz) = ((px - 0.5).max(0.0), (pz - 0.5).max(0.0));
+        let (x0, z0) = (fx as u32, fz as u32);
+        let (tx, tz) = (fx.fract(), fz.fract());
+
+        let top = sample(x0, z0) * (1.0 - tx) + sample(x0 + 1, z0) * tx;
+        let bottom = sample(x0, z0 + 1) * (1.0 - tx) + sample(x0 + 1, z0 + 1) * tx;
+        let value = top * (1.0 - tz) + bottom * tz;
+
+        Some(self.origin.y + (value * self.vertical_scale).round() as i32)
+    }
+
+    /// Block on top of the column at world `x`, `z`
+    fn surface_block(&self, x: i32, z: i32) -> Voxel {
+        let Some(colors) = &self.colors else {
+            return Blocks::GRASS_BLOCK.default_state();
+        };
+
+        let (px, pz) = self.pixel(x, z);
+        let (width, height) = self.heights.dimensions();
+
+        let cx = (px / width as f32 * colors.width() as f32) as u32;
+        let cz = (pz / height as f32 * colors.height() as f32) as u32;
+        let color = colors.get_pixel(cx.min(colors.width() - 1), cz.min(colors.height() - 1));
+
+        Blocks::nearest_color(color.0).default_state()
+    }
+}
+
+impl Generator for HeightmapGenerator {
+    fn generate(&self, chunk: &mut Chunk) {
+        let chunk_min: WorldCoord = chunk.coord.into();
+
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let (wx, wz) = (chunk_min.x + x as i32, chunk_min.z + z as i32);
+
+                let Some(surface) = self.height(wx, wz) else {
+                    continue;
+                };
+
+                if surface < chunk_min.y {
+                    continue;
+                }
+
+                let top = self.surface_block(wx, wz);
+                let soil = if top == Blocks::GRASS_BLOCK.default_state() {
+                    Blocks::DIRT_BLOCK.default_state()
+                } else {
+                    top
+                };
+
+                for y in 0..CHUNK_SIZE {
+                    let depth = surface - (chunk_min.y + y as i32);
+
+                    let voxel = match depth {
+                        ..0 => break,
+                        0 => top,
+                        d if d <= Self::SOIL_DEPTH => soil,
+                        _ => Blocks::STONE.default_state(),
+                    };
+
+                    chunk.set_voxel(ChunkLocalCoord { x, y, z }, voxel);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+
+use super::{edit::WorldEdit, World};
+
+struct HistoryEntry {
+    before: WorldEdit,
+    after: WorldEdit,
+}
+
+impl HistoryEntry {
+    fn memory_size(&self) -> usize {
+        self.before.memory_size() + self.after.memory_size()
+    }
+}
+
+/// Undo/redo stacks of user edits, oldest entries are dropped
+/// once they take more than `memory_limit` bytes.
+pub struct EditHistory {
+    undo: VecDeque<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+    memory_limit: usize,
+    memory_used: usize,
+}
+
+#[allow(dead_code)]
+impl EditHistory {
+    pub const DEFAULT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+    pub fn new(memory_limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            memory_limit,
+            memory_used: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.memory_used = 0;
+    }
+
+    pub fn undo_count(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_count(&self) -> usize {
+        self.redo.len()
+    }
+
+    pub fn memory_used(&self) -> usize {
+        self.memory_used
+    }
+
+    fn push(&mut self, entry: HistoryEntry) {
+        for dropped in self.redo.drain(..) {
+            self.memory_used -= dropped.memory_size();
+        }
+
+        self.memory_used += entry.memory_size();
+        self.undo.push_back(entry);
+
+        // Always keep the latest entry, even if it's over the limit by itself
+        while self.memory_used > self.memory_limit && self.undo.len() > 1 {
+            let dropped = self.undo.pop_front().unwrap();
+            self.memory_used -= dropped.memory_size();
+        }
+    }
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MEMORY_LIMIT)
+    }
+}
+
+#[allow(dead_code)]
+impl<T> World<T> {
+    /// Applies `edit` and records it so it can be undone.
+    pub fn apply_user_edit(&mut self, edit: &WorldEdit) {
+        let before = self.apply_edit(edit);
+
+        // Only keep the writes that landed in loaded chunks
+        let mut after = WorldEdit::new();
+        for (coord, voxel) in edit.iter() {
+            if before.get(coord).is_some() {
+                after.set(coord, voxel);
+            }
+        }
+
+        if before
+            .iter()
+            .all(|(coord, voxel)| after.get(coord) == Some(voxel))
+        {
+            return;
+        }
+
+        self.history.push(HistoryEntry { before, after });
+    }
+
+    /// Reverts the latest user edit. Returns false if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(entry) = self.history.undo.pop_back() else {
+            return false;
+        };
+
+        self.apply_edit(&entry.before);
+        self.history.redo.push(entry);
+
+        true
+    }
+
+    /// Reapplies the latest undone edit. Returns false if there was nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(entry) = self.history.redo.pop() else {
+            return false;
+        };
+
+        self.apply_edit(&entry.after);
+        self.history.undo.push_back(entry);
+
+        true
+    }
+
+    pub fn history(&self) -> &EditHistory {
+        &self.history
+    }
+}
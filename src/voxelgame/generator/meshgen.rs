@@ -1,17 +1,14 @@
 use crate::voxelgame::{
-    generator::{chunk::Chunk, WorldAccessor},
+    atlas,
+    generator::{chunk::Chunk, World, WorldAccessor},
     mesh::{MeshInfo, Vertex3d},
 };
 
 use super::{
-    chunk::{BlockOffsetCoord, WorldCoord, CHUNK_SIZE},
-    voxel::{Blocks, Voxel},
+    chunk::{BlockOffsetCoord, ChunkCoord, WorldCoord, CHUNK_SIZE},
+    voxel::{BlockBox, BlockModel, Blocks, Voxel},
 };
 
-pub const TEXTURE_COUNT: (usize, usize) = (32, 32);
-pub const TEXTURE_UV_STEP: (f32, f32) =
-    (1.0 / TEXTURE_COUNT.0 as f32, 1.0 / TEXTURE_COUNT.1 as f32);
-
 #[derive(Clone, Copy, Debug)]
 enum FaceOrientation {
     Left,
@@ -33,239 +30,161 @@ impl FaceOrientation {
             Self::Front => 5,
         }
     }
+
+    fn opposite(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            Self::Top => Self::Bottom,
+            Self::Bottom => Self::Top,
+            Self::Back => Self::Front,
+            Self::Front => Self::Back,
+        }
+    }
+
+    /// Axis the face is perpendicular to and whether it faces the positive direction
+    fn axis(self) -> (usize, bool) {
+        match self {
+            Self::Left => (0, false),
+            Self::Right => (0, true),
+            Self::Bottom => (1, false),
+            Self::Top => (1, true),
+            Self::Front => (2, false),
+            Self::Back => (2, true),
+        }
+    }
 }
 
-const fn texture_offset(texture_id: usize) -> (f32, f32) {
-    (
-        (texture_id % TEXTURE_COUNT.0) as f32 * TEXTURE_UV_STEP.0,
-        (texture_id / TEXTURE_COUNT.0) as f32 * TEXTURE_UV_STEP.1,
-    )
+/// Whether the face of `block_box` lies on the side of the block
+fn on_side(block_box: &BlockBox, side: FaceOrientation) -> bool {
+    match side.axis() {
+        (axis, true) => block_box.max[axis] >= 1.0,
+        (axis, false) => block_box.min[axis] <= 0.0,
+    }
 }
 
-fn face(
+/// Whether `model` covers the whole side of the block
+fn covers_side(model: BlockModel, side: FaceOrientation) -> bool {
+    let (axis, _) = side.axis();
+
+    model.boxes().iter().any(|block_box| {
+        on_side(block_box, side)
+            && (0..3)
+                .filter(|other| *other != axis)
+                .all(|other| block_box.min[other] <= 0.0 && block_box.max[other] >= 1.0)
+    })
+}
+
+/// Whether the face on `side` of `current` is hidden by the `neighbor` block
+fn is_hidden(current: Voxel, neighbor: Voxel, side: FaceOrientation) -> bool {
+    let neighbor_info = Blocks::BLOCKS[neighbor.id as usize];
+
+    // Faces between two of the same transparent block, like leaves, are hidden
+    if neighbor_info.transparent && neighbor != current {
+        return false;
+    }
+
+    covers_side(neighbor_info.model, side.opposite())
+}
+
+/// Face of `block_box` in the block at `offset`, showing the part of the
+/// texture the face covers so that textures line up across boxes
+fn box_face(
     texture_id: usize,
     offset: (usize, usize, usize),
+    block_box: &BlockBox,
     orientation: FaceOrientation,
 ) -> ([Vertex3d; 4], [u32; 6]) {
-    let texture_offset = texture_offset(texture_id);
-    match orientation {
+    let ([x0, y0, z0], [x1, y1, z1]) = (block_box.min, block_box.max);
+
+    let (corners, normal, indices) = match orientation {
         FaceOrientation::Back => (
-            [
-                Vertex3d {
-                    position: [offset.0 as f32, offset.1 as f32, offset.2 as f32 + 1.0],
-                    normal: [0.0, 0.0, 1.0],
-                    uv: [
-                        texture_offset.0 + TEXTURE_UV_STEP.0,
-                        texture_offset.1 + TEXTURE_UV_STEP.1,
-                    ],
-                },
-                Vertex3d {
-                    position: [
-                        offset.0 as f32 + 1.0,
-                        offset.1 as f32,
-                        offset.2 as f32 + 1.0,
-                    ],
-                    normal: [0.0, 0.0, 1.0],
-                    uv: [texture_offset.0, texture_offset.1 + TEXTURE_UV_STEP.1],
-                },
-                Vertex3d {
-                    position: [
-                        offset.0 as f32 + 1.0,
-                        offset.1 as f32 + 1.0,
-                        offset.2 as f32 + 1.0,
-                    ],
-                    normal: [0.0, 0.0, 1.0],
-                    uv: [texture_offset.0, texture_offset.1],
-                },
-                Vertex3d {
-                    position: [
-                        offset.0 as f32,
-                        offset.1 as f32 + 1.0,
-                        offset.2 as f32 + 1.0,
-                    ],
-                    normal: [0.0, 0.0, 1.0],
-                    uv: [texture_offset.0 + TEXTURE_UV_STEP.0, texture_offset.1],
-                },
-            ],
+            [[x0, y0, z1], [x1, y0, z1], [x1, y1, z1], [x0, y1, z1]],
+            [0.0, 0.0, 1.0],
             [0, 1, 2, 0, 2, 3],
         ),
         FaceOrientation::Front => (
-            [
-                Vertex3d {
-                    position: [offset.0 as f32, offset.1 as f32, offset.2 as f32],
-                    normal: [0.0, 0.0, -1.0],
-                    uv: [texture_offset.0, texture_offset.1 + TEXTURE_UV_STEP.1],
-                },
-                Vertex3d {
-                    position: [offset.0 as f32 + 1.0, offset.1 as f32, offset.2 as f32],
-                    normal: [0.0, 0.0, -1.0],
-                    uv: [
-                        texture_offset.0 + TEXTURE_UV_STEP.0,
-                        texture_offset.1 + TEXTURE_UV_STEP.1,
-                    ],
-                },
-                Vertex3d {
-                    position: [
-                        offset.0 as f32 + 1.0,
-                        offset.1 as f32 + 1.0,
-                        offset.2 as f32,
-                    ],
-                    normal: [0.0, 0.0, -1.0],
-                    uv: [texture_offset.0 + TEXTURE_UV_STEP.0, texture_offset.1],
-                },
-                Vertex3d {
-                    position: [offset.0 as f32, offset.1 as f32 + 1.0, offset.2 as f32],
-                    normal: [0.0, 0.0, -1.0],
-                    uv: [texture_offset.0, texture_offset.1],
-                },
-            ],
+            [[x0, y0, z0], [x1, y0, z0], [x1, y1, z0], [x0, y1, z0]],
+            [0.0, 0.0, -1.0],
             [0, 2, 1, 0, 3, 2],
         ),
         FaceOrientation::Left => (
-            [
-                Vertex3d {
-                    position: [offset.0 as f32, offset.1 as f32, offset.2 as f32 + 1.0],
-                    normal: [-1.0, 0.0, 0.0],
-                    uv: [texture_offset.0, texture_offset.1 + TEXTURE_UV_STEP.1],
-                },
-                Vertex3d {
-                    position: [offset.0 as f32, offset.1 as f32, offset.2 as f32],
-                    normal: [-1.0, 0.0, 0.0],
-                    uv: [
-                        texture_offset.0 + TEXTURE_UV_STEP.0,
-                        texture_offset.1 + TEXTURE_UV_STEP.1,
-                    ],
-                },
-                Vertex3d {
-                    position: [offset.0 as f32, offset.1 as f32 + 1.0, offset.2 as f32],
-                    normal: [-1.0, 0.0, 0.0],
-                    uv: [texture_offset.0 + TEXTURE_UV_STEP.0, texture_offset.1],
-                },
-                Vertex3d {
-                    position: [
-                        offset.0 as f32,
-                        offset.1 as f32 + 1.0,
-                        offset.2 as f32 + 1.0,
-                    ],
-                    normal: [-1.0, 0.0, 0.0],
-                    uv: [texture_offset.0, texture_offset.1],
-                },
-            ],
+            [[x0, y0, z1], [x0, y0, z0], [x0, y1, z0], [x0, y1, z1]],
+            [-1.0, 0.0, 0.0],
             [0, 2, 1, 0, 3, 2],
         ),
         FaceOrientation::Right => (
-            [
-                Vertex3d {
-                    position: [offset.0 as f32 + 1.0, offset.1 as f32, offset.2 as f32],
-                    normal: [1.0, 0.0, 0.0],
-                    uv: [texture_offset.0, texture_offset.1 + TEXTURE_UV_STEP.1],
-                },
-                Vertex3d {
-                    position: [
-                        offset.0 as f32 + 1.0,
-                        offset.1 as f32,
-                        offset.2 as f32 + 1.0,
-                    ],
-                    normal: [1.0, 0.0, 0.0],
-                    uv: [
-                        texture_offset.0 + TEXTURE_UV_STEP.0,
-                        texture_offset.1 + TEXTURE_UV_STEP.1,
-                    ],
-                },
-                Vertex3d {
-                    position: [
-                        offset.0 as f32 + 1.0,
-                        offset.1 as f32 + 1.0,
-                        offset.2 as f32 + 1.0,
-                    ],
-                    normal: [1.0, 0.0, 0.0],
-                    uv: [texture_offset.0 + TEXTURE_UV_STEP.0, texture_offset.1],
-                },
-                Vertex3d {
-                    position: [
-                        offset.0 as f32 + 1.0,
-                        offset.1 as f32 + 1.0,
-                        offset.2 as f32,
-                    ],
-                    normal: [1.0, 0.0, 0.0],
-                    uv: [texture_offset.0, texture_offset.1],
-                },
-            ],
+            [[x1, y0, z0], [x1, y0, z1], [x1, y1, z1], [x1, y1, z0]],
+            [1.0, 0.0, 0.0],
             [0, 2, 1, 0, 3, 2],
         ),
         FaceOrientation::Bottom => (
-            [
-                Vertex3d {
-                    position: [offset.0 as f32, offset.1 as f32, offset.2 as f32],
-                    normal: [0.0, -1.0, 0.0],
-                    uv: [texture_offset.0, texture_offset.1 + TEXTURE_UV_STEP.1],
-                },
-                Vertex3d {
-                    position: [offset.0 as f32 + 1.0, offset.1 as f32, offset.2 as f32],
-                    normal: [0.0, -1.0, 0.0],
-                    uv: [
-                        texture_offset.0 + TEXTURE_UV_STEP.0,
-                        texture_offset.1 + TEXTURE_UV_STEP.1,
-                    ],
-                },
-                Vertex3d {
-                    position: [
-                        offset.0 as f32 + 1.0,
-                        offset.1 as f32,
-                        offset.2 as f32 + 1.0,
-                    ],
-                    normal: [0.0, -1.0, 0.0],
-                    uv: [texture_offset.0 + TEXTURE_UV_STEP.0, texture_offset.1],
-                },
-                Vertex3d {
-                    position: [offset.0 as f32, offset.1 as f32, offset.2 as f32 + 1.0],
-                    normal: [0.0, -1.0, 0.0],
-                    uv: [texture_offset.0, texture_offset.1],
-                },
-            ],
+            [[x0, y0, z0], [x1, y0, z0], [x1, y0, z1], [x0, y0, z1]],
+            [0.0, -1.0, 0.0],
             [0, 1, 2, 0, 2, 3],
         ),
         FaceOrientation::Top => (
-            [
-                Vertex3d {
-                    position: [offset.0 as f32, offset.1 as f32 + 1.0, offset.2 as f32],
-                    normal: [0.0, 1.0, 0.0],
-                    uv: [texture_offset.0, texture_offset.1 + TEXTURE_UV_STEP.1],
-                },
-                Vertex3d {
-                    position: [
-                        offset.0 as f32 + 1.0,
-                        offset.1 as f32 + 1.0,
-                        offset.2 as f32,
-                    ],
-                    normal: [0.0, 1.0, 0.0],
-                    uv: [
-                        texture_offset.0 + TEXTURE_UV_STEP.0,
-                        texture_offset.1 + TEXTURE_UV_STEP.1,
-                    ],
-                },
-                Vertex3d {
-                    position: [
-                        offset.0 as f32 + 1.0,
-                        offset.1 as f32 + 1.0,
-                        offset.2 as f32 + 1.0,
-                    ],
-                    normal: [0.0, 1.0, 0.0],
-                    uv: [texture_offset.0 + TEXTURE_UV_STEP.0, texture_offset.1],
-                },
-                Vertex3d {
-                    position: [
-                        offset.0 as f32,
-                        offset.1 as f32 + 1.0,
-                        offset.2 as f32 + 1.0,
-                    ],
-                    normal: [0.0, 1.0, 0.0],
-                    uv: [texture_offset.0, texture_offset.1],
-                },
-            ],
+            [[x0, y1, z0], [x1, y1, z0], [x1, y1, z1], [x0, y1, z1]],
+            [0.0, 1.0, 0.0],
             [0, 2, 1, 0, 3, 2],
         ),
-    }
+    };
+
+    // Texture coordinates within the tile, V pointing down
+    let uv = |[x, y, z]: [f32; 3]| match orientation {
+        FaceOrientation::Back => (1.0 - x, 1.0 - y),
+        FaceOrientation::Front => (x, 1.0 - y),
+        FaceOrientation::Left => (1.0 - z, 1.0 - y),
+        FaceOrientation::Right => (z, 1.0 - y),
+        FaceOrientation::Bottom | FaceOrientation::Top => (x, 1.0 - z),
+    };
+
+    let vertices = corners.map(|corner| {
+        let (u, v) = uv(corner);
+
+        Vertex3d {
+            position: [
+                offset.0 as f32 + corner[0],
+                offset.1 as f32 + corner[1],
+                offset.2 as f32 + corner[2],
+            ],
+            normal,
+            uv: [u, v],
+            layer: texture_id as u32,
+        }
+    });
+
+    (vertices, indices)
+}
+
+/// Two quads crossing diagonally through the block, visible from both sides
+fn cross(texture_id: usize, offset: (usize, usize, usize)) -> ([Vertex3d; 8], [u32; 24]) {
+    let (x, y, z) = (offset.0 as f32, offset.1 as f32, offset.2 as f32);
+
+    // Lit like the ground they grow on
+    let vertex = |position: [f32; 3], u: f32, v: f32| Vertex3d {
+        position,
+        normal: [0.0, 1.0, 0.0],
+        uv: [u, v],
+        layer: texture_id as u32,
+    };
+
+    (
+        [
+            vertex([x, y, z], 0.0, 1.0),
+            vertex([x + 1.0, y, z + 1.0], 1.0, 1.0),
+            vertex([x + 1.0, y + 1.0, z + 1.0], 1.0, 0.0),
+            vertex([x, y + 1.0, z], 0.0, 0.0),
+            vertex([x + 1.0, y, z], 0.0, 1.0),
+            vertex([x, y, z + 1.0], 1.0, 1.0),
+            vertex([x, y + 1.0, z + 1.0], 1.0, 0.0),
+            vertex([x + 1.0, y + 1.0, z], 0.0, 0.0),
+        ],
+        [
+            0, 1, 2, 0, 2, 3, 0, 2, 1, 0, 3, 2, // First quad, both sides
+            4, 5, 6, 4, 6, 7, 4, 6, 5, 4, 7, 6, // Second quad
+        ],
+    )
 }
 
 #[inline]
@@ -312,7 +231,10 @@ pub fn generate_mesh_lod(
     lod_level: LodLevel,
 ) -> Option<MeshInfo<Vertex3d>> {
     let mut vertices = Vec::new();
-    let mut indices = Vec::new();
+    // Indices by the direction faces point in, so faces turned away from the camera can be skipped.
+    // `FaceOrientation::to_texture_id` is in the same order as `ChunkVertex::NORMALS`
+    let mut faces: [Vec<u32>; 6] = Default::default();
+    let mut any_direction = Vec::new();
 
     let step = lod_level.to_step_size();
     let scale = step as f32;
@@ -332,9 +254,22 @@ pub fn generate_mesh_lod(
                 let current_voxel =
                     get_voxel_wrapper(&chunk, coord, &world_accessor).unwrap_or_default();
                 let current_block_info = Blocks::BLOCKS[current_voxel.id as usize];
+                let textures = atlas::layout().block_textures(current_voxel.id as usize);
 
-                if current_block_info.transparent {
-                    continue;
+                match current_block_info.model {
+                    BlockModel::None => continue,
+                    BlockModel::Cross => {
+                        // Too small to be worth drawing at a lower detail
+                        if step == 1 {
+                            let (vx, idx) =
+                                cross(textures[0], (x as usize, y as usize, z as usize));
+                            idx.into_iter()
+                                .for_each(|i| any_direction.push(i + vertices.len() as u32));
+                            vertices.extend(vx);
+                        }
+                        continue;
+                    }
+                    _ => {}
                 }
 
                 const SIDES: [FaceOrientation; 6] = [
@@ -346,7 +281,7 @@ pub fn generate_mesh_lod(
                     FaceOrientation::Front,
                 ];
 
-                for side in SIDES {
+                let hidden = SIDES.map(|side| {
                     let coord = match side {
                         FaceOrientation::Left => coord.left(step as i32),
                         FaceOrientation::Right => coord.right(step as i32),
@@ -359,14 +294,23 @@ pub fn generate_mesh_lod(
                     let voxel =
                         get_voxel_wrapper(&chunk, coord, &world_accessor).unwrap_or_default();
 
-                    let block_info = Blocks::BLOCKS[voxel.id as usize];
+                    is_hidden(current_voxel, voxel, side)
+                });
+
+                for block_box in current_block_info.model.boxes() {
+                    for (side, hidden) in SIDES.into_iter().zip(hidden) {
+                        // Faces inside the block can't be hidden by neighbours
+                        if hidden && on_side(block_box, side) {
+                            continue;
+                        }
 
-                    if block_info.transparent {
-                        let (mut vx, idx) = face(
-                            current_block_info.texture_ids[side.to_texture_id()],
+                        let (mut vx, idx) = box_face(
+                            textures[side.to_texture_id()],
                             (x as usize / step, y as usize / step, z as usize / step),
+                            block_box,
                             side,
                         );
+                        let indices = &mut faces[side.to_texture_id()];
                         idx.into_iter()
                             .for_each(|i| indices.push(i + vertices.len() as u32));
                         vx.iter_mut().for_each(|v| {
@@ -385,5 +329,42 @@ pub fn generate_mesh_lod(
         return None;
     }
 
-    Some(MeshInfo { vertices, indices })
+    Some(MeshInfo::from_faces(vertices, faces, any_direction))
+}
+
+#[allow(dead_code)]
+impl<T> World<T> {
+    /// Meshes every loaded chunk between `min` and `max` inclusive on the
+    /// calling thread, ordered by coordinate. Chunks without faces are left out.
+    pub fn chunk_meshes(
+        &self,
+        min: ChunkCoord,
+        max: ChunkCoord,
+    ) -> Vec<(ChunkCoord, MeshInfo<Vertex3d>)> {
+        let mut chunks: Vec<Box<Chunk>> = {
+            let lock = self.chunks.lock().unwrap();
+
+            lock.values()
+                .filter(|chunk| {
+                    let c = chunk.coord;
+
+                    (min.x..=max.x).contains(&c.x)
+                        && (min.y..=max.y).contains(&c.y)
+                        && (min.z..=max.z).contains(&c.z)
+                })
+                .cloned()
+                .collect()
+        };
+        chunks.sort_by_key(|chunk| (chunk.coord.x, chunk.coord.y, chunk.coord.z));
+
+        chunks
+            .into_iter()
+            .filter_map(|chunk| {
+                let coord = chunk.coord;
+                let mesh = generate_mesh_lod(chunk, self.world_accessor.clone(), LodLevel::_0)?;
+
+                Some((coord, mesh))
+            })
+            .collect()
+    }
 }
@@ -1,12 +1,28 @@
+pub mod carver;
 pub mod chunk;
+pub mod config;
+pub mod edit;
+pub mod feature;
+pub mod flat;
+pub mod heightmap;
+pub mod history;
 pub mod meshgen;
+pub mod query;
+pub mod raycast;
+pub mod schematic;
+pub mod spec;
+pub mod structure;
+pub mod vegetation;
+pub mod visibility;
+pub mod vox;
 pub mod voxel;
 
 use std::{
+    cell::Cell,
     collections::{HashMap, HashSet, VecDeque},
     sync::{
         mpsc::{self, Receiver, Sender},
-        Arc, Mutex,
+        Arc, Mutex, RwLock,
     },
     thread::{self, JoinHandle},
     time::Duration,
@@ -15,38 +31,49 @@ use std::{
 use cgmath::{EuclideanSpace, MetricSpace};
 use fastnoise_lite::FastNoiseLite;
 
+use carver::{Carver, CarverKind};
 use chunk::{Chunk, ChunkCoord, ChunkLocalCoord, WorldCoord, CHUNK_SIZE};
-use rand::Rng;
+use config::{DensityConfig, NoiseConfig, NoiseGeneratorConfig};
+use edit::WorldEdit;
+use feature::{Biome, Feature, Terrain};
+use history::EditHistory;
+use vegetation::VegetationFeature;
+use visibility::ChunkConnectivity;
 use voxel::{Blocks, Voxel};
 
 use crate::voxelgame::{
-    generator::{chunk::BlockOffsetCoord, meshgen::generate_mesh_lod},
-    mesh::{MeshInfo, Vertex3d},
+    generator::meshgen::generate_mesh_lod,
+    mesh::{ChunkVertex, MeshInfo},
 };
 
 use super::{
     camera::Camera,
+    chunk_buffers::{ChunkBuffers, ChunkDrawMode},
     debug::{DebugDrawer, ModelName},
-    draw::{Drawable, Model},
-    mesh::Mesh,
 };
 
 pub trait Generator: Sync + Send {
     fn generate(&self, _chunk: &mut Chunk) {}
 }
 
+impl Generator for Box<dyn Generator> {
+    fn generate(&self, chunk: &mut Chunk) {
+        self.as_ref().generate(chunk);
+    }
+}
+
 struct NoiseSampler {
-    // noise: Fbm<noise::Simplex>,
     noise: FastNoiseLite,
+    /// Offsets sample positions when domain warp is enabled
+    warp: Option<FastNoiseLite>,
 }
 
 impl NoiseSampler {
-    pub fn new(seed: i32) -> Self {
-        Self {
-            noise: FastNoiseLite::with_seed(seed),
-        }
-    }
+    pub fn new(seed: i32, config: &NoiseConfig) -> Self {
+        let (noise, warp) = config.build(seed);
 
+        Self { noise, warp }
+    }
 
     /// Samples a noise value at 2d coordinates discarding Y of `chunk_coord`.
     /// 
@@ -59,58 +86,151 @@ impl NoiseSampler {
     /// 
     /// Returns value between `-1.0..1.0`.
     pub fn sample_3d(&self, chunk_coord: ChunkCoord, x: f32, y: f32, z: f32, scale: f32) -> f32 {
-        const OCTAVES: usize = 4;
-
-        let mut total = 0.0;
-        for i in 0..OCTAVES {
-            let scale = scale * 2.0f32.powi(i as i32);
-            let magnitude = 1.0 / 2.0f32.powi(i as i32 + 1);
-
-            total += self.noise.get_noise_3d(
-                ((chunk_coord.x as f32 * CHUNK_SIZE as f32) + x) * scale,
-                ((chunk_coord.y as f32 * CHUNK_SIZE as f32) + y) * scale,
-                ((chunk_coord.z as f32 * CHUNK_SIZE as f32) + z) * scale,
-            ) * magnitude;
+        self.sample_world(
+            (
+                (chunk_coord.x as f32 * CHUNK_SIZE as f32) + x,
+                (chunk_coord.y as f32 * CHUNK_SIZE as f32) + y,
+                (chunk_coord.z as f32 * CHUNK_SIZE as f32) + z,
+            ),
+            scale,
+        )
+    }
+
+    /// Samples a noise value at world coordinates.
+    ///
+    /// Returns value between `-1.0..1.0`.
+    pub fn sample_world(&self, mut position: (f32, f32, f32), scale: f32) -> f32 {
+        if let Some(warp) = &self.warp {
+            position = warp.domain_warp_3d(position.0, position.1, position.2);
         }
 
-        total
+        self.noise
+            .get_noise_3d(position.0 * scale, position.1 * scale, position.2 * scale)
     }
 }
 
 pub struct NoiseGenerator {
+    seed: i32,
+    config: NoiseGeneratorConfig,
     sampler: NoiseSampler,
+    /// Warp of the density noise, see `DensityConfig::domain_warp`
+    density_warp: Option<FastNoiseLite>,
+    carvers: [Carver; 2],
+    features: Vec<Box<dyn Feature>>,
 }
 
+#[allow(dead_code)]
 impl NoiseGenerator {
     pub fn new(seed: i32) -> Self {
+        Self::with_config(seed, NoiseGeneratorConfig::default())
+    }
+
+    pub fn with_config(seed: i32, config: NoiseGeneratorConfig) -> Self {
+        let density_warp = config
+            .density
+            .as_ref()
+            .and_then(|density| density.domain_warp.as_ref())
+            .map(|warp| warp.build(seed.wrapping_add(2)));
+
+        let features = VegetationFeature::library()
+            .into_iter()
+            .map(|feature| {
+                Box::new(feature.with_density(config.vegetation_density)) as Box<dyn Feature>
+            })
+            .collect();
+
         Self {
-            sampler: NoiseSampler::new(seed),
+            seed,
+            sampler: NoiseSampler::new(seed, &config.noise),
+            density_warp,
+            carvers: [
+                Carver::new(CarverKind::Worm, config.worms.clone(), seed),
+                Carver::new(CarverKind::Ravine, config.ravines.clone(), seed),
+            ],
+            config,
+            features,
         }
     }
-}
 
-impl Generator for NoiseGenerator {
-    fn generate(&self, chunk: &mut Chunk) {
-        const SCALE: f32 = 0.3;
+    pub fn config(&self) -> &NoiseGeneratorConfig {
+        &self.config
+    }
+
+    /// Adds a feature placed after the terrain, in the order they were added
+    pub fn with_feature(mut self, feature: impl Feature + 'static) -> Self {
+        self.features.push(Box::new(feature));
+        self
+    }
+
+    /// Height of the topmost terrain block at world X and Z, ignoring caves.
+    ///
+    /// Only approximate with density terrain, which ignores overhangs.
+    pub fn surface_height(&self, x: i32, z: i32) -> i32 {
+        let origin = ChunkCoord { x: 0, y: 0, z: 0 };
 
-        let mut rng = rand::rng();
+        self.height_sample(origin, x as f32, z as f32) as i32
+    }
 
+    fn height_sample(&self, chunk_coord: ChunkCoord, x: f32, z: f32) -> f32 {
+        self.config.base_height
+            + self.sampler.sample_2d(chunk_coord, x, z, self.config.scale)
+                * self.config.height_amplitude
+    }
+
+    /// Whether the block at world `position` is solid with density terrain,
+    /// given the height noise of its column
+    pub fn is_dense(&self, density: &DensityConfig, position: WorldCoord, height: f32) -> bool {
+        let mut sample = (position.x as f32, position.y as f32, position.z as f32);
+
+        if let Some(warp) = &self.density_warp {
+            sample = warp.domain_warp_3d(sample.0, sample.1, sample.2);
+        }
+
+        // Positive below the height noise, reaching 1 at `height_amplitude` away from it
+        let bias = (height - position.y as f32) / self.config.height_amplitude.max(f32::EPSILON);
+
+        bias * density.squash_factor + self.sampler.sample_world(sample, density.scale) > 0.0
+    }
+
+    fn is_cave(&self, chunk_coord: ChunkCoord, coord: ChunkLocalCoord) -> bool {
+        let (x, y, z) = (coord.x as f32, coord.y as f32, coord.z as f32);
+
+        let cave_sample = self
+            .sampler
+            .sample_3d(chunk_coord, x, y, z, self.config.cave_scale);
+
+        let caviness = self
+            .sampler
+            .sample_3d(chunk_coord, x, y, z, self.config.caviness_scale)
+            * 0.5
+            + 0.5;
+
+        cave_sample * (1.0 - caviness) < self.config.cave_threshold
+    }
+
+    /// Block `depth` blocks below the nearest air above it
+    fn layer_block(&self, depth: u32, sandy: f32) -> Voxel {
+        if depth == 0 {
+            if sandy < self.config.sand_threshold {
+                Blocks::SAND.default_state()
+            } else {
+                Blocks::GRASS_BLOCK.default_state()
+            }
+        } else if depth <= self.config.dirt_depth {
+            Blocks::DIRT_BLOCK.default_state()
+        } else {
+            Blocks::STONE.default_state()
+        }
+    }
+
+    fn generate_heightfield(&self, chunk: &mut Chunk) {
         for x in 0..CHUNK_SIZE {
             for z in 0..CHUNK_SIZE {
-                let height_sample = 40.0
-                    + self.sampler.sample_2d(
-                        chunk.coord,
-                        x as f32,
-                        z as f32,
-                        SCALE,
-                    ) * 90.0;
-                
-                let sandy = self.sampler.sample_2d(
-                    chunk.coord,
-                    x as f32,
-                    z as f32,
-                    SCALE * 6.0,
-                );
+                let height_sample = self.height_sample(chunk.coord, x as f32, z as f32);
+
+                let sandy =
+                    self.sampler
+                        .sample_2d(chunk.coord, x as f32, z as f32, self.config.sand_scale);
 
                 for y in 0..CHUNK_SIZE {
                     let wy = chunk.coord.y as i32 * CHUNK_SIZE as i32 + y as i32;
@@ -118,64 +238,123 @@ impl Generator for NoiseGenerator {
                     let coord = ChunkLocalCoord { x, y, z };
 
                     if wy <= height_sample as i32 {
-                        let cave_sample = self.sampler.sample_3d(
-                            chunk.coord,
-                            x as f32,
-                            y as f32,
-                            z as f32,
-                            SCALE * 4.0,
-                        );
-
-                        let caviness = self.sampler.sample_3d(
-                            chunk.coord,
-                            x as f32,
-                            y as f32,
-                            z as f32,
-                            SCALE * 0.3,
-                        ) * 0.5
-                            + 0.5;
-
-                        if cave_sample * (1.0 - caviness) < -0.23 {
+                        if self.is_cave(chunk.coord, coord) {
                             continue;
                         }
 
-                        if wy - height_sample as i32 >= 0 {
-                            if rng.random_bool(0.001) {
-                                for k in 0..10 {
-                                    chunk.set_voxel(
-                                        ChunkLocalCoord {
-                                            x: coord.x,
-                                            y: coord.y + k,
-                                            z: coord.z,
-                                        },
-                                        Blocks::LOG.default_state(),
-                                    );
-                                }
-                            }
-                            
-                            if sandy < -0.5 {
-                                chunk.set_voxel(coord, Blocks::SAND.default_state());
-                            } else {
-                                chunk.set_voxel(coord, Blocks::GRASS_BLOCK.default_state());
-                            }
-                        } else if wy - height_sample as i32 >= -2 {
-                            chunk.set_voxel(coord, Blocks::DIRT_BLOCK.default_state());
-                        } else {
-                            chunk.set_voxel(coord, Blocks::STONE.default_state());
-                        }
+                        let depth = (height_sample as i32 - wy) as u32;
+                        chunk.set_voxel(coord, self.layer_block(depth, sandy));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Layers blocks like the heightfield does, counting depth from the
+    /// nearest air above instead of from the height noise
+    fn generate_density(&self, chunk: &mut Chunk, density: &DensityConfig) {
+        let chunk_min: WorldCoord = chunk.coord.into();
+
+        // Blocks above the chunk needed to tell how deep its top blocks are
+        let above = self.config.dirt_depth as usize + 1;
+
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let height_sample = self.height_sample(chunk.coord, x as f32, z as f32);
+
+                let sandy =
+                    self.sampler
+                        .sample_2d(chunk.coord, x as f32, z as f32, self.config.sand_scale);
+
+                // Unknown until air is found, which only happens deep underground
+                let mut depth: Option<u32> = None;
+
+                for y in (0..CHUNK_SIZE + above).rev() {
+                    let position = WorldCoord {
+                        x: chunk_min.x + x as i32,
+                        y: chunk_min.y + y as i32,
+                        z: chunk_min.z + z as i32,
+                    };
+
+                    if !self.is_dense(density, position, height_sample) {
+                        depth = Some(0);
+                        continue;
                     }
+
+                    let block_depth = depth.unwrap_or(u32::MAX);
+                    depth = depth.map(|depth| depth + 1);
+
+                    let coord = ChunkLocalCoord { x, y, z };
+                    if y >= CHUNK_SIZE || self.is_cave(chunk.coord, coord) {
+                        continue;
+                    }
+
+                    chunk.set_voxel(coord, self.layer_block(block_depth, sandy));
                 }
             }
         }
     }
 }
 
+impl Generator for NoiseGenerator {
+    fn generate(&self, chunk: &mut Chunk) {
+        match &self.config.density {
+            Some(density) => self.generate_density(chunk, density),
+            None => self.generate_heightfield(chunk),
+        }
+
+        for carver in self.carvers.iter() {
+            carver.carve(chunk);
+        }
+
+        for feature in self.features.iter() {
+            feature.place(chunk, self.seed, self);
+        }
+    }
+}
+
+impl Terrain for NoiseGenerator {
+    fn surface_height(&self, x: i32, z: i32) -> i32 {
+        NoiseGenerator::surface_height(self, x, z)
+    }
+
+    fn surface_block(&self, x: i32, z: i32) -> Voxel {
+        match self.biome(x, z) {
+            Biome::Desert => Blocks::SAND.default_state(),
+            _ => Blocks::GRASS_BLOCK.default_state(),
+        }
+    }
+
+    fn biome(&self, x: i32, z: i32) -> Biome {
+        let origin = ChunkCoord { x: 0, y: 0, z: 0 };
+        let (x, z) = (x as f32, z as f32);
+
+        if self.sampler.sample_2d(origin, x, z, self.config.sand_scale) < self.config.sand_threshold
+        {
+            return Biome::Desert;
+        }
+
+        // Offset to not follow the height noise sampled at a similar scale
+        let forest =
+            self.sampler
+                .sample_2d(origin, x + 10000.0, z - 10000.0, self.config.forest_scale);
+
+        if forest > self.config.forest_threshold {
+            Biome::Forest
+        } else {
+            Biome::Plains
+        }
+    }
+}
+
 pub struct Ray {
     pub origin: cgmath::Point3<f32>,
     pub direction: cgmath::Vector3<f32>,
 }
 
 type Queue<T> = VecDeque<T>;
+/// A meshed chunk, `None` when it has no faces, and which of its faces connect
+type MeshResult = (ChunkCoord, Option<MeshInfo<ChunkVertex>>, ChunkConnectivity);
 
 #[derive(Clone)]
 pub struct WorldAccessor {
@@ -194,10 +373,18 @@ impl WorldAccessor {
 }
 
 pub struct World<T> {
-    generator: Arc<T>,
+    generator: Arc<RwLock<T>>,
     chunks: Arc<Mutex<HashMap<ChunkCoord, Box<Chunk>>>>,
     world_accessor: WorldAccessor,
-    models: HashMap<ChunkCoord, Model<Mesh>>,
+    /// Uploaded chunk meshes, created with the first mesh
+    meshes: Option<ChunkBuffers>,
+    draw_mode: ChunkDrawMode,
+    /// Face connectivity of every meshed chunk, including the ones without a mesh
+    connectivity: HashMap<ChunkCoord, ChunkConnectivity>,
+    occlusion_culling: bool,
+    history: EditHistory,
+    /// Bumped whenever voxels are edited, so copies of world contents know they're stale
+    revision: u64,
 
     chunk_gen_queue: Arc<Mutex<Queue<ChunkCoord>>>,
     meshgen_queue: Arc<Mutex<Queue<ChunkCoord>>>,
@@ -210,15 +397,15 @@ pub struct World<T> {
 
     chunk_receiver: Receiver<Box<Chunk>>,
     chunk_sender: Sender<Box<Chunk>>,
-    mesh_receiver: Receiver<(ChunkCoord, Option<MeshInfo<Vertex3d>>)>,
-    mesh_sender: Sender<(ChunkCoord, Option<MeshInfo<Vertex3d>>)>,
+    mesh_receiver: Receiver<MeshResult>,
+    mesh_sender: Sender<MeshResult>,
 }
 
 #[allow(dead_code)]
 impl<T> World<T> {
     pub fn new(generator: T) -> Self {
         let (ctx, crx) = mpsc::channel::<Box<Chunk>>();
-        let (mtx, mrx) = mpsc::channel::<(ChunkCoord, Option<MeshInfo<Vertex3d>>)>();
+        let (mtx, mrx) = mpsc::channel::<MeshResult>();
 
         let chunks = Arc::new(Mutex::new(HashMap::new()));
         let world_accessor = WorldAccessor {
@@ -226,11 +413,16 @@ impl<T> World<T> {
         };
 
         Self {
-            generator: Arc::new(generator),
+            generator: Arc::new(RwLock::new(generator)),
             chunks,
             world_accessor,
 
-            models: HashMap::new(),
+            meshes: None,
+            draw_mode: ChunkDrawMode::Loop,
+            connectivity: HashMap::new(),
+            occlusion_culling: true,
+            history: EditHistory::default(),
+            revision: 0,
             chunk_gen_queue: Arc::new(Mutex::new(Queue::new())),
 
             meshgen_queue: Arc::new(Mutex::new(Queue::new())),
@@ -252,13 +444,38 @@ impl<T> World<T> {
         let mut sent = self.loaded_chunks.lock().unwrap();
         let mut genqueue = self.chunk_gen_queue.lock().unwrap();
         let mut meshqueue = self.meshgen_queue.lock().unwrap();
+        let mut meshed = self.meshed_chunks.lock().unwrap();
 
         sent.clear();
         genqueue.clear();
         meshqueue.clear();
+        meshed.clear();
 
         self.chunks.lock().unwrap().clear();
-        self.models.clear();
+        if let Some(meshes) = self.meshes.as_mut() {
+            meshes.clear();
+        }
+        self.connectivity.clear();
+        self.history.clear();
+        self.revision += 1;
+
+        // Drop whatever was generated or meshed before the reset
+        self.chunk_receiver.try_iter().for_each(drop);
+        self.mesh_receiver.try_iter().for_each(drop);
+    }
+
+    /// Replaces the generator and regenerates the world with it
+    pub fn set_generator(&mut self, generator: T) {
+        let lock = self.generator.clone();
+        let mut current = lock.write().unwrap();
+        *current = generator;
+
+        // Still holding the lock so no chunk from the old generator arrives after the reset
+        self.reset();
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
     }
 
     pub fn get_voxel(&self, position: WorldCoord) -> Option<Voxel> {
@@ -271,117 +488,14 @@ impl<T> World<T> {
     }
 
     pub fn set_voxels_radius(&mut self, center: WorldCoord, radius: u32, block: Voxel) {
-        let mut chunks_affected: HashSet<ChunkCoord> = HashSet::new();
-        let radius = radius as i32;
-
-        let mut lock = self.chunks.lock().unwrap();
-
-        for i in -radius..radius {
-            for j in -radius..radius {
-                for k in -radius..radius {
-                    let offset_float = (i as f32, j as f32, k as f32);
-                    let dst_float = offset_float.0 * offset_float.0
-                        + offset_float.1 * offset_float.1
-                        + offset_float.2 * offset_float.2;
-
-                    if dst_float > (radius * radius) as f32 {
-                        continue;
-                    }
-
-                    let offset = BlockOffsetCoord { x: i, y: j, z: k };
-                    let world_coord = center + offset;
-                    let chunk_coord: ChunkCoord = world_coord.into();
-                    let local_coord: ChunkLocalCoord = world_coord.into();
-                    let chunk = lock.get_mut(&chunk_coord);
-
-                    if let Some(chunk) = chunk {
-                        chunk.set_voxel(local_coord, block);
-
-                        if local_coord.left().is_none() && lock.contains_key(&chunk_coord.left()) {
-                            chunks_affected.insert(chunk_coord.left());
-                        }
-
-                        if local_coord.right().is_none() && lock.contains_key(&chunk_coord.right())
-                        {
-                            chunks_affected.insert(chunk_coord.right());
-                        }
-
-                        if local_coord.up().is_none() && lock.contains_key(&chunk_coord.up()) {
-                            chunks_affected.insert(chunk_coord.up());
-                        }
-
-                        if local_coord.down().is_none() && lock.contains_key(&chunk_coord.down()) {
-                            chunks_affected.insert(chunk_coord.down());
-                        }
-
-                        if local_coord.front().is_none() && lock.contains_key(&chunk_coord.front())
-                        {
-                            chunks_affected.insert(chunk_coord.front());
-                        }
-
-                        if local_coord.back().is_none() && lock.contains_key(&chunk_coord.back()) {
-                            chunks_affected.insert(chunk_coord.back());
-                        }
-
-                        chunks_affected.insert(chunk_coord);
-                    }
-                }
-            }
-        }
-
-        let mut lock = self.meshgen_queue.lock().unwrap();
-        chunks_affected.iter().for_each(|c| lock.push_front(*c));
-
-        let mut lock = self.meshed_chunks.lock().unwrap();
-        chunks_affected.iter().for_each(|c| _ = lock.remove(c));
+        self.apply_edit(&WorldEdit::sphere(center, radius, block));
     }
 
     pub fn set_voxel(&mut self, position: WorldCoord, block: Voxel) {
-        // TODO: Remove code duplication
-        let chunk_coord: ChunkCoord = position.into();
-        let local_coord: ChunkLocalCoord = position.into();
-
-        let mut lock = self.chunks.lock().unwrap();
-
-        let chunk = lock.get_mut(&chunk_coord);
-
-        let mut chunks_to_remesh = Vec::new();
-
-        if let Some(chunk) = chunk {
-            chunk.set_voxel(local_coord, block);
-
-            if let None = local_coord.left() {
-                chunks_to_remesh.push(chunk.coord.left());
-            }
-
-            if let None = local_coord.right() {
-                chunks_to_remesh.push(chunk.coord.right());
-            }
-
-            if let None = local_coord.up() {
-                chunks_to_remesh.push(chunk.coord.up());
-            }
-
-            if let None = local_coord.down() {
-                chunks_to_remesh.push(chunk.coord.down());
-            }
-
-            if let None = local_coord.front() {
-                chunks_to_remesh.push(chunk.coord.front());
-            }
-
-            if let None = local_coord.back() {
-                chunks_to_remesh.push(chunk.coord.back());
-            }
-
-            chunks_to_remesh.push(chunk.coord);
-        }
+        let mut edit = WorldEdit::new();
+        edit.set(position, block);
 
-        let mut lock = self.meshgen_queue.lock().unwrap();
-        chunks_to_remesh.iter().for_each(|c| lock.push_front(*c));
-
-        let mut lock = self.meshed_chunks.lock().unwrap();
-        chunks_to_remesh.iter().for_each(|c| _ = lock.remove(c));
+        self.apply_edit(&edit);
     }
 
     pub fn get_chunk_count(&self) -> usize {
@@ -466,11 +580,15 @@ impl<T> World<T> {
 
                 if let Some(chunk_to_generate) = chunk_to_generate {
                     let mut chunk = Box::new(Chunk::new(chunk_to_generate));
+
+                    // Held until sent so that `set_generator` can't reset in between
+                    let generator = generator.read().unwrap();
                     generator.generate(&mut chunk);
 
                     log::debug!("Generated chunk {}", chunk_to_generate);
 
                     tx.send(chunk).expect("Channel was closed");
+                    drop(generator);
                 } else {
                     thread::sleep(Duration::from_millis(1));
                 }
@@ -485,12 +603,23 @@ impl<T> World<T> {
                 let coord: Option<ChunkCoord> = mesh_gen_queue.lock().unwrap().pop_front();
 
                 if let Some(mesh_to_gen) = coord {
-                    let chunk = world_accessor.chunks.lock().unwrap()[&mesh_to_gen].clone();
+                    // Gone if the world was reset after it was queued
+                    let Some(chunk) = world_accessor
+                        .chunks
+                        .lock()
+                        .unwrap()
+                        .get(&mesh_to_gen)
+                        .cloned()
+                    else {
+                        continue;
+                    };
+                    let connectivity = ChunkConnectivity::compute(&chunk);
                     let mesh =
-                        generate_mesh_lod(chunk, world_accessor.clone(), meshgen::LodLevel::_0);
+                        generate_mesh_lod(chunk, world_accessor.clone(), meshgen::LodLevel::_0)
+                            .map(ChunkVertex::pack_mesh);
                     log::debug!("Finished meshing {}!", mesh_to_gen);
 
-                    tx.send((mesh_to_gen, mesh)).unwrap();
+                    tx.send((mesh_to_gen, mesh, connectivity)).unwrap();
                 } else {
                     thread::sleep(Duration::from_millis(1));
                 }
@@ -513,43 +642,9 @@ impl<T> World<T> {
         }
     }
 
-    pub fn ray_hit(
-        &self,
-        ray: Ray,
-        mut debug: Option<&mut DebugDrawer>,
-    ) -> Option<(WorldCoord, cgmath::Point3<f32>, Voxel)> {
-        const MAX_DISTANCE: f32 = 256.0;
-
-        let mut distance = 0.0;
-
-        while distance < MAX_DISTANCE {
-            let point = ray.origin + ray.direction * distance;
-
-            let world_coord: WorldCoord = point.to_vec().into();
-
-            if let Some(debug) = debug.as_mut() {
-                debug.append_mesh(
-                    ModelName::Cube,
-                    point.to_vec() - cgmath::Vector3::new(0.05, 0.05, 0.05),
-                    cgmath::Vector3::new(0.1, 0.1, 0.1),
-                    cgmath::Vector4::new(1.0, 0.0, 1.0, 0.3),
-                );
-            }
-
-            let result = self.get_voxel(world_coord);
-
-            distance += 0.1;
-
-            if let Some(voxel) = result {
-                if !Blocks::BLOCKS[voxel.id as usize].solid {
-                    continue;
-                }
-
-                return Some((world_coord, point, voxel));
-            }
-        }
-
-        None
+    /// Inserts an already generated chunk, bypassing the worldgen threads.
+    pub fn insert_chunk(&mut self, chunk: Box<Chunk>) {
+        self.chunks.lock().unwrap().insert(chunk.coord, chunk);
     }
 
     pub fn dequeue_meshgen(
@@ -559,17 +654,19 @@ impl<T> World<T> {
         queue: &wgpu::Queue,
         bg_layout: &wgpu::BindGroupLayout,
     ) {
-        for (i, (coord, mesh)) in self.mesh_receiver.try_iter().enumerate() {
+        let meshes = self
+            .meshes
+            .get_or_insert_with(|| ChunkBuffers::new(device, bg_layout));
+
+        for (i, (coord, mesh, connectivity)) in self.mesh_receiver.try_iter().enumerate() {
+            self.connectivity.insert(coord, connectivity);
+
             if let Some(mesh) = mesh {
                 log::debug!("Received mesh for chunk {}", coord);
 
-                let mut model = Model::new(bg_layout, device, Mesh::from_info(device, mesh));
-                model.position = coord.into();
-                model.update_buffer(queue);
-
-                _ = self.models.insert(coord, model);
+                meshes.insert(device, queue, bg_layout, coord, &mesh);
             } else {
-                _ = self.models.remove(&coord);
+                meshes.remove(&coord);
             }
 
             if i >= limit {
@@ -581,7 +678,8 @@ impl<T> World<T> {
     pub fn unload_distance(&mut self, eye: cgmath::Vector3<f32>, max_distance_chunks: usize) {
         let mut coords_to_delete: Vec<ChunkCoord> = Vec::new();
 
-        for coord in self.models.keys() {
+        // Every meshed chunk has connectivity, even the ones without faces
+        for coord in self.connectivity.keys() {
             let position: cgmath::Vector3<f32> = (*coord).into();
 
             if position.distance(eye) > max_distance_chunks as f32 * CHUNK_SIZE as f32 {
@@ -589,24 +687,67 @@ impl<T> World<T> {
             }
         }
 
-        coords_to_delete
-            .iter()
-            .for_each(|c| _ = self.models.remove(c));
+        for coord in &coords_to_delete {
+            self.connectivity.remove(coord);
+            if let Some(meshes) = self.meshes.as_mut() {
+                meshes.remove(coord);
+            }
+        }
 
         let mut lock = self.meshed_chunks.lock().unwrap();
         coords_to_delete.iter().for_each(|c| _ = lock.remove(c));
     }
 
-    // Returns a number of chunks drawn
+    /// How chunks are drawn, see `ChunkDrawMode::supported`
+    pub fn set_draw_mode(&mut self, mode: ChunkDrawMode) {
+        self.draw_mode = mode;
+    }
+
+    pub fn draw_mode(&self) -> ChunkDrawMode {
+        self.draw_mode
+    }
+
+    /// Whether chunks walled off from the camera are skipped, see `visibility::visible_chunks`
+    pub fn set_occlusion_culling(&mut self, enabled: bool) {
+        self.occlusion_culling = enabled;
+    }
+
+    pub fn occlusion_culling(&self) -> bool {
+        self.occlusion_culling
+    }
+
+    /// Draws the chunks near `camera` inside its frustum,
+    /// leaving out the faces of each chunk that point away from the camera.
+    ///
+    /// Returns the number of chunks drawn, of chunks in range culled by the frustum
+    /// and of chunks in the frustum the camera can't see into through other chunks.
     pub fn draw_distance(
-        &self,
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
         render_pass: &mut wgpu::RenderPass,
-        eye: cgmath::Vector3<f32>,
+        camera: &Camera,
         max_distance_chunks: usize,
-    ) -> usize {
-        let mut count = 0;
-        for (coord, model) in self.models.iter() {
-            let position: cgmath::Vector3<f32> = (*coord).into();
+    ) -> (usize, usize, usize) {
+        let eye = camera.eye.to_vec();
+        let reachable = self.occlusion_culling.then(|| {
+            visibility::visible_chunks(
+                WorldCoord::from(eye).into(),
+                max_distance_chunks as i32,
+                |coord| self.connectivity.get(&coord).copied(),
+            )
+        });
+
+        let Some(meshes) = self.meshes.as_mut() else {
+            return (0, 0, 0);
+        };
+
+        let frustum = camera.frustum();
+        let culled = Cell::new(0);
+        let occluded = Cell::new(0);
+
+        let visible = |coord: ChunkCoord| {
+            let position: cgmath::Vector3<f32> = coord.into();
 
             let h_position = cgmath::Vector3::new(position.x, 0.0, position.z);
             let h_eye = cgmath::Vector3::new(eye.x, 0.0, eye.z);
@@ -614,17 +755,34 @@ impl<T> World<T> {
             if h_position.distance(h_eye) > (max_distance_chunks as f32 * CHUNK_SIZE as f32)
                 || (position.y - eye.y).abs() > (max_distance_chunks as f32 * CHUNK_SIZE as f32)
             {
-                continue;
+                return None;
             }
-            // log::debug!("Drawing chunk at {}", coord);
-            model.draw(render_pass);
-            count += 1;
-        }
-        count
+
+            let size = CHUNK_SIZE as f32;
+            if !frustum.intersects_aabb(position, position + cgmath::Vector3::new(size, size, size))
+            {
+                culled.set(culled.get() + 1);
+                return None;
+            }
+
+            if reachable
+                .as_ref()
+                .is_some_and(|reachable| !reachable.contains(&coord))
+            {
+                occluded.set(occluded.get() + 1);
+                return None;
+            }
+
+            Some(visibility::facing_faces(coord, eye))
+        };
+
+        let drawn = meshes.draw(device, queue, render_pass, self.draw_mode, visible);
+        (drawn, culled.get(), occluded.get())
     }
 
-    pub fn append_debug(&self, debug: &mut DebugDrawer) {
-        for (coord, _) in self.models.iter() {
+    /// Outlines every chunk with a mesh
+    pub fn append_debug_outlines(&self, debug: &mut DebugDrawer) {
+        for (coord, _) in self.meshes.iter().flat_map(ChunkBuffers::iter) {
             let position = (*coord).into(); // rust being weird again
 
             let scale =
@@ -637,7 +795,9 @@ impl<T> World<T> {
                 [1.0, 0.0, 1.0, 0.0].into(),
             );
         }
+    }
 
+    pub fn append_debug(&self, debug: &mut DebugDrawer) {
         let meshgen_queue_text = format!(
             "Meshgen Queue size: {}",
             self.meshgen_queue.lock().unwrap().len(),
@@ -647,9 +807,22 @@ impl<T> World<T> {
             self.chunk_gen_queue.lock().unwrap().len(),
         );
         let chunks_count_text = format!("Chunk count: {}", self.chunks.lock().unwrap().len(),);
-        let mesh_count_text = format!("Loaded meshes count: {}", self.models.len(),);
+        let mesh_count_text = format!(
+            "Loaded meshes count: {}",
+            self.meshes.as_ref().map_or(0, ChunkBuffers::len),
+        );
+        let (used, allocated) = self.meshes.as_ref().map_or((0, 0), ChunkBuffers::memory);
+        let mesh_memory_text = format!("Chunk buffers: {} / {} KiB", used / 1024, allocated / 1024);
+        let history_text = format!(
+            "Undo: {} Redo: {} ({} KiB)",
+            self.history.undo_count(),
+            self.history.redo_count(),
+            self.history.memory_used() / 1024,
+        );
         debug.set_text("chunks.count", chunks_count_text);
         debug.set_text("models.count", mesh_count_text);
+        debug.set_text("models.memory", mesh_memory_text);
+        debug.set_text("world.history", history_text);
         debug.set_text("world.meshgen_queue_size", meshgen_queue_text);
         debug.set_text("world.worldgen_queue_size", chunk_queue_text);
     }
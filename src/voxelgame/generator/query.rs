@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use super::{
+    chunk::{Chunk, ChunkCoord, ChunkLocalCoord, WorldCoord, CHUNK_SIZE},
+    voxel::{Blocks, Voxel},
+    World,
+};
+
+/// Axis aligned bounding box in world space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: cgmath::Vector3<f32>,
+    pub max: cgmath::Vector3<f32>,
+}
+
+#[allow(dead_code)]
+impl Aabb {
+    pub fn new(min: cgmath::Vector3<f32>, max: cgmath::Vector3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// Unit box occupied by a voxel
+    pub fn from_voxel(coord: WorldCoord) -> Self {
+        let min: cgmath::Vector3<f32> = coord.into();
+
+        Self {
+            min,
+            max: min + cgmath::Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn size(&self) -> cgmath::Vector3<f32> {
+        self.max - self.min
+    }
+
+    pub fn translated(&self, offset: cgmath::Vector3<f32>) -> Self {
+        Self {
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+
+    /// Returns true if boxes overlap with a non-zero volume.
+    /// Boxes that only touch don't intersect.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x < other.max.x
+            && self.max.x > other.min.x
+            && self.min.y < other.max.y
+            && self.max.y > other.min.y
+            && self.min.z < other.max.z
+            && self.max.z > other.min.z
+    }
+
+    pub fn inflated(&self, amount: f32) -> Self {
+        let amount = cgmath::Vector3::new(amount, amount, amount);
+
+        Self {
+            min: self.min - amount,
+            max: self.max + amount,
+        }
+    }
+
+    /// Box covering both this box and this box moved by `offset`
+    pub fn expanded_towards(&self, offset: cgmath::Vector3<f32>) -> Self {
+        let moved = self.translated(offset);
+
+        Self {
+            min: cgmath::Vector3::new(
+                self.min.x.min(moved.min.x),
+                self.min.y.min(moved.min.y),
+                self.min.z.min(moved.min.z),
+            ),
+            max: cgmath::Vector3::new(
+                self.max.x.max(moved.max.x),
+                self.max.y.max(moved.max.y),
+                self.max.z.max(moved.max.z),
+            ),
+        }
+    }
+
+    /// Clips `offset` along `axis` (0 = X, 1 = Y, 2 = Z) so that `other`,
+    /// moving by it, doesn't end up inside this box.
+    pub fn clip_offset(&self, other: &Aabb, axis: usize, offset: f32) -> f32 {
+        // Boxes have to overlap on the remaining two axes to collide
+        for i in 0..3 {
+            if i != axis && (other.max[i] <= self.min[i] || other.min[i] >= self.max[i]) {
+                return offset;
+            }
+        }
+
+        if offset > 0.0 && other.max[axis] <= self.min[axis] {
+            offset.min(self.min[axis] - other.max[axis])
+        } else if offset < 0.0 && other.min[axis] >= self.max[axis] {
+            offset.max(self.max[axis] - other.min[axis])
+        } else {
+            offset
+        }
+    }
+
+    /// Inclusive range of voxels this box overlaps
+    pub fn voxel_range(&self) -> (WorldCoord, WorldCoord) {
+        let min = WorldCoord {
+            x: self.min.x.floor() as i32,
+            y: self.min.y.floor() as i32,
+            z: self.min.z.floor() as i32,
+        };
+        let max = WorldCoord {
+            x: self.max.x.ceil() as i32 - 1,
+            y: self.max.y.ceil() as i32 - 1,
+            z: self.max.z.ceil() as i32 - 1,
+        };
+
+        (min, max)
+    }
+}
+
+/// Calls `f` for every loaded voxel in the inclusive region, chunk by chunk.
+fn for_each_in_region(
+    chunks: &HashMap<ChunkCoord, Box<Chunk>>,
+    min: WorldCoord,
+    max: WorldCoord,
+    mut f: impl FnMut(WorldCoord, Voxel),
+) {
+    if min.x > max.x || min.y > max.y || min.z > max.z {
+        return;
+    }
+
+    let min_chunk: ChunkCoord = min.into();
+    let max_chunk: ChunkCoord = max.into();
+
+    for cx in min_chunk.x..=max_chunk.x {
+        for cy in min_chunk.y..=max_chunk.y {
+            for cz in min_chunk.z..=max_chunk.z {
+                let chunk_coord = ChunkCoord {
+                    x: cx,
+                    y: cy,
+                    z: cz,
+                };
+
+                let Some(chunk) = chunks.get(&chunk_coord) else {
+                    continue;
+                };
+
+                let origin: WorldCoord = chunk_coord.into();
+                let last = CHUNK_SIZE as i32 - 1;
+
+                let lx = (min.x - origin.x).max(0)..=(max.x - origin.x).min(last);
+                let ly = (min.y - origin.y).max(0)..=(max.y - origin.y).min(last);
+                let lz = (min.z - origin.z).max(0)..=(max.z - origin.z).min(last);
+
+                for z in lz {
+                    for y in ly.clone() {
+                        for x in lx.clone() {
+                            let local = ChunkLocalCoord {
+                                x: x as usize,
+                                y: y as usize,
+                                z: z as usize,
+                            };
+
+                            if let Some(voxel) = chunk.get_voxel(local) {
+                                f(
+                                    WorldCoord {
+                                        x: origin.x + x,
+                                        y: origin.y + y,
+                                        z: origin.z + z,
+                                    },
+                                    voxel,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_solid(voxel: Voxel) -> bool {
+    Blocks::BLOCKS[voxel.id as usize].solid
+}
+
+#[allow(dead_code)]
+impl<T> World<T> {
+    /// Visits every voxel in the inclusive region between `min` and `max`.
+    /// Voxels in chunks that aren't loaded are skipped.
+    pub fn for_each_voxel_in_region(
+        &self,
+        min: WorldCoord,
+        max: WorldCoord,
+        f: impl FnMut(WorldCoord, Voxel),
+    ) {
+        let lock = self.chunks.lock().unwrap();
+        for_each_in_region(&lock, min, max, f);
+    }
+
+    /// Returns all loaded voxels whose unit box overlaps `aabb`, including air.
+    pub fn voxels_in_aabb(&self, aabb: &Aabb) -> Vec<(WorldCoord, Voxel)> {
+        let (min, max) = aabb.voxel_range();
+        let mut result = Vec::new();
+
+        self.for_each_voxel_in_region(min, max, |coord, voxel| result.push((coord, voxel)));
+
+        result
+    }
+
+    /// Returns all solid voxels whose unit box overlaps `aabb`.
+    pub fn solid_voxels_in_aabb(&self, aabb: &Aabb) -> Vec<(WorldCoord, Voxel)> {
+        let (min, max) = aabb.voxel_range();
+        let mut result = Vec::new();
+
+        self.for_each_voxel_in_region(min, max, |coord, voxel| {
+            if is_solid(voxel) {
+                result.push((coord, voxel));
+            }
+        });
+
+        result
+    }
+
+    /// Returns all solid voxels whose unit box overlaps the sphere.
+    pub fn solid_voxels_in_sphere(
+        &self,
+        center: cgmath::Point3<f32>,
+        radius: f32,
+    ) -> Vec<(WorldCoord, Voxel)> {
+        let extent = cgmath::Vector3::new(radius, radius, radius);
+        let center = cgmath::Vector3::new(center.x, center.y, center.z);
+        let bounds = Aabb::new(center - extent, center + extent);
+
+        let (min, max) = bounds.voxel_range();
+        let mut result = Vec::new();
+
+        self.for_each_voxel_in_region(min, max, |coord, voxel| {
+            if !is_solid(voxel) {
+                return;
+            }
+
+            // Closest point of the voxel to the center of the sphere
+            let voxel_min: cgmath::Vector3<f32> = coord.into();
+            let closest = cgmath::Vector3::new(
+                center.x.clamp(voxel_min.x, voxel_min.x + 1.0),
+                center.y.clamp(voxel_min.y, voxel_min.y + 1.0),
+                center.z.clamp(voxel_min.z, voxel_min.z + 1.0),
+            );
+            let d = closest - center;
+
+            if d.x * d.x + d.y * d.y + d.z * d.z < radius * radius {
+                result.push((coord, voxel));
+            }
+        });
+
+        result
+    }
+
+    /// Returns Y of the highest solid voxel in the column at `x`, `z`
+    /// out of all loaded chunks, or `None` if there is none.
+    pub fn column_height(&self, x: i32, z: i32) -> Option<i32> {
+        let lock = self.chunks.lock().unwrap();
+
+        let column: ChunkCoord = WorldCoord { x, y: 0, z }.into();
+        let local: ChunkLocalCoord = WorldCoord { x, y: 0, z }.into();
+
+        let mut chunks_in_column: Vec<&Chunk> = lock
+            .iter()
+            .filter(|(c, _)| c.x == column.x && c.z == column.z)
+            .map(|(_, chunk)| chunk.as_ref())
+            .collect();
+        chunks_in_column.sort_by_key(|chunk| -chunk.coord.y);
+
+        for chunk in chunks_in_column {
+            for y in (0..CHUNK_SIZE).rev() {
+                let voxel = chunk.get_voxel(ChunkLocalCoord { y, ..local });
+
+                if voxel.is_some_and(is_solid) {
+                    let origin: WorldCoord = chunk.coord.into();
+                    return Some(origin.y + y as i32);
+                }
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,168 @@
+use cgmath::{EuclideanSpace, InnerSpace};
+
+use super::{
+    chunk::{BlockOffsetCoord, ChunkCoord, ChunkLocalCoord, WorldCoord},
+    voxel::{Blocks, Voxel},
+    Ray, World,
+};
+use crate::voxelgame::debug::{DebugDrawer, ModelName};
+
+/// Which voxels stop a ray.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RayFilter {
+    /// Only blocks marked as `solid` in the registry
+    Solid,
+    /// Anything that isn't air
+    NonAir,
+}
+
+impl RayFilter {
+    fn accepts(self, voxel: Voxel) -> bool {
+        match self {
+            Self::Solid => Blocks::BLOCKS[voxel.id as usize].solid,
+            Self::NonAir => voxel.id != Blocks::AIR.default_state().id,
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    /// Voxel that was hit
+    pub coord: WorldCoord,
+    /// Point where the ray entered the voxel
+    pub point: cgmath::Point3<f32>,
+    /// Distance along the (normalized) ray to `point`
+    pub distance: f32,
+    /// Normal of the face the ray entered through.
+    ///
+    /// Zero if the ray started inside the voxel.
+    pub normal: BlockOffsetCoord,
+    pub voxel: Voxel,
+}
+
+impl<T> World<T> {
+    /// Casts a ray through the voxel grid using Amanatides-Woo traversal,
+    /// visiting every voxel the ray passes through exactly once.
+    ///
+    /// Voxels in unloaded chunks are treated as empty.
+    pub fn ray_hit(
+        &self,
+        ray: Ray,
+        max_distance: f32,
+        filter: RayFilter,
+        mut debug: Option<&mut DebugDrawer>,
+    ) -> Option<RayHit> {
+        if ray.direction.magnitude2() == 0.0 {
+            return None;
+        }
+
+        let direction = ray.direction.normalize();
+        let origin = ray.origin.to_vec();
+
+        let mut coord: WorldCoord = origin.into();
+
+        let step = [
+            direction.x.signum() as i32,
+            direction.y.signum() as i32,
+            direction.z.signum() as i32,
+        ];
+
+        // Distance along the ray between two voxel boundaries on each axis
+        let t_delta = [
+            (1.0 / direction.x).abs(),
+            (1.0 / direction.y).abs(),
+            (1.0 / direction.z).abs(),
+        ];
+
+        // Distance along the ray to the first voxel boundary on each axis
+        let first_boundary = |o: f32, d: f32, c: i32| {
+            if d > 0.0 {
+                (c as f32 + 1.0 - o) / d
+            } else if d < 0.0 {
+                (c as f32 - o) / d
+            } else {
+                f32::INFINITY
+            }
+        };
+
+        let mut t_max = [
+            first_boundary(origin.x, direction.x, coord.x),
+            first_boundary(origin.y, direction.y, coord.y),
+            first_boundary(origin.z, direction.z, coord.z),
+        ];
+
+        let lock = self.chunks.lock().unwrap();
+        let lookup = |coord: WorldCoord| {
+            let chunk_coord: ChunkCoord = coord.into();
+            let local_coord: ChunkLocalCoord = coord.into();
+
+            lock.get(&chunk_coord)?.get_voxel(local_coord)
+        };
+
+        let mut distance = 0.0;
+        let mut normal = BlockOffsetCoord::default();
+
+        loop {
+            let point = ray.origin + direction * distance;
+
+            if let Some(debug) = debug.as_mut() {
+                debug.append_mesh(
+                    ModelName::Cube,
+                    point.to_vec() - cgmath::Vector3::new(0.05, 0.05, 0.05),
+                    cgmath::Vector3::new(0.1, 0.1, 0.1),
+                    cgmath::Vector4::new(1.0, 0.0, 1.0, 0.3),
+                );
+            }
+
+            if let Some(voxel) = lookup(coord) {
+                if filter.accepts(voxel) {
+                    return Some(RayHit {
+                        coord,
+                        point,
+                        distance,
+                        normal,
+                        voxel,
+                    });
+                }
+            }
+
+            let axis = if t_max[0] < t_max[1] {
+                if t_max[0] < t_max[2] {
+                    0
+                } else {
+                    2
+                }
+            } else if t_max[1] < t_max[2] {
+                1
+            } else {
+                2
+            };
+
+            distance = t_max[axis];
+
+            if distance > max_distance {
+                return None;
+            }
+
+            t_max[axis] += t_delta[axis];
+
+            normal = BlockOffsetCoord::default();
+            match axis {
+                0 => {
+                    coord.x += step[0];
+                    normal.x = -step[0];
+                }
+                1 => {
+                    coord.y += step[1];
+                    normal.y = -step[1];
+                }
+                _ => {
+                    coord.z += step[2];
+                    normal.z = -step[2];
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,557 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    io::{Read, Write},
+    path::Path,
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use super::{
+    structure::Structure,
+    voxel::{Blocks, Voxel},
+};
+
+#[derive(Debug, Clone)]
+pub struct SchematicError {
+    pub message: String,
+}
+
+impl SchematicError {
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl Display for SchematicError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SchematicError {}
+type SchematicResult<T> = Result<T, SchematicError>;
+
+/// Structure saved to disk together with free-form metadata
+/// such as its name or author.
+///
+/// Files are gzip compressed. Inside, all numbers are little endian:
+///
+/// ```text
+/// magic       b"VXSCHEM\0"
+/// version     u16
+/// size        u32 x 3
+/// palette     u16 count, then block names as u16 length + UTF-8
+/// data        u8 bits per voxel, u32 word count, then u64 words
+/// metadata    u16 count, then key and value as u16 length + UTF-8
+/// ```
+///
+/// Voxels are palette indices packed into the words without spanning
+/// two words, in the same order as `Structure::iter`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schematic {
+    pub structure: Structure,
+    pub metadata: BTreeMap<String, String>,
+}
+
+#[allow(dead_code)]
+impl Schematic {
+    const MAGIC: &'static [u8; 8] = b"VXSCHEM\0";
+    const VERSION: u16 = 1;
+
+    pub fn new(structure: Structure) -> Self {
+        Self {
+            structure,
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> SchematicResult<()> {
+        std::fs::write(path, self.to_bytes())
+            .map_err(|e| SchematicError::new(format!("Failed to write file: {e}")))
+    }
+
+    /// Loads either our own format or a Sponge `.schem` file
+    pub fn load(path: impl AsRef<Path>) -> SchematicResult<Self> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| SchematicError::new(format!("Failed to read file: {e}")))?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut palette: Vec<Voxel> = Vec::new();
+        let mut indices = Vec::with_capacity(self.structure.voxels().len());
+
+        for voxel in self.structure.voxels() {
+            let index = match palette.iter().position(|v| v == voxel) {
+                Some(index) => index,
+                None => {
+                    palette.push(*voxel);
+                    palette.len() - 1
+                }
+            };
+            indices.push(index as u64);
+        }
+
+        let mut data = Vec::new();
+        data.extend_from_slice(Self::MAGIC);
+        data.extend_from_slice(&Self::VERSION.to_le_bytes());
+
+        for side in self.structure.size() {
+            data.extend_from_slice(&(side as u32).to_le_bytes());
+        }
+
+        data.extend_from_slice(&(palette.len() as u16).to_le_bytes());
+        for voxel in palette.iter() {
+            write_string(&mut data, Blocks::BLOCKS[voxel.id as usize].name);
+        }
+
+        let bits = bits_per_entry(palette.len());
+        let words = pack(&indices, bits);
+        data.push(bits as u8);
+        data.extend_from_slice(&(words.len() as u32).to_le_bytes());
+        for word in words {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+
+        data.extend_from_slice(&(self.metadata.len() as u16).to_le_bytes());
+        for (key, value) in self.metadata.iter() {
+            write_string(&mut data, key);
+            write_string(&mut data, value);
+        }
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Parses either our own format or a Sponge `.schem` file,
+    /// both of which are gzip compressed.
+    pub fn from_bytes(bytes: &[u8]) -> SchematicResult<Self> {
+        let mut data = Vec::new();
+        GzDecoder::new(bytes)
+            .read_to_end(&mut data)
+            .map_err(|e| SchematicError::new(format!("Failed to decompress: {e}")))?;
+
+        if data.starts_with(Self::MAGIC) {
+            Self::parse(&data)
+        } else {
+            Self::parse_sponge(&data)
+        }
+    }
+
+    fn parse(data: &[u8]) -> SchematicResult<Self> {
+        let mut reader = ByteReader::new(data);
+        reader.take(Self::MAGIC.len())?;
+
+        let version = u16::from_le_bytes(reader.array()?);
+        if version != Self::VERSION {
+            return Err(SchematicError::new(format!(
+                "Unsupported schematic version {version}"
+            )));
+        }
+
+        let mut size = [0; 3];
+        for side in size.iter_mut() {
+            *side = u32::from_le_bytes(reader.array()?) as usize;
+        }
+
+        let palette_len = u16::from_le_bytes(reader.array()?) as usize;
+        let mut palette = Vec::with_capacity(palette_len);
+        for _ in 0..palette_len {
+            let name = reader.string()?;
+            let block = Blocks::by_name(&name)
+                .ok_or_else(|| SchematicError::new(format!("Unknown block \"{name}\"")))?;
+            palette.push(block.default_state());
+        }
+
+        let bits = reader.array::<1>()?[0] as usize;
+        let word_count = u32::from_le_bytes(reader.array()?) as usize;
+        let mut words = Vec::new();
+        for _ in 0..word_count {
+            words.push(u64::from_le_bytes(reader.array()?));
+        }
+
+        // Checked before allocating anything of that size
+        let count = size
+            .iter()
+            .try_fold(1usize, |count, side| count.checked_mul(*side))
+            .ok_or_else(|| SchematicError::new("Schematic is too large".to_string()))?;
+        let indices = unpack(&words, bits, count)
+            .ok_or_else(|| SchematicError::new("Voxel data is too short".to_string()))?;
+        let mut structure = Structure::new(size);
+
+        for (i, index) in indices.into_iter().enumerate() {
+            let voxel = *palette.get(index as usize).ok_or_else(|| {
+                SchematicError::new(format!("Palette index {index} out of range"))
+            })?;
+
+            let (x, y, z) = (
+                i % size[0],
+                (i / size[0]) % size[1],
+                i / (size[0] * size[1]),
+            );
+            structure.set(x, y, z, voxel);
+        }
+
+        let metadata_len = u16::from_le_bytes(reader.array()?) as usize;
+        let mut metadata = BTreeMap::new();
+        for _ in 0..metadata_len {
+            let key = reader.string()?;
+            let value = reader.string()?;
+            metadata.insert(key, value);
+        }
+
+        Ok(Self {
+            structure,
+            metadata,
+        })
+    }
+
+    /// Parses a decompressed Sponge schematic, versions 1 to 3.
+    ///
+    /// Block states are mapped to our blocks by name, dropping the namespace and
+    /// properties. Anything without a match becomes air.
+    fn parse_sponge(data: &[u8]) -> SchematicResult<Self> {
+        let mut reader = ByteReader::new(data);
+        let root = match nbt::read_root(&mut reader)? {
+            nbt::Tag::Compound(root) => root,
+            _ => return Err(SchematicError::new("Root tag isn't a compound".to_string())),
+        };
+
+        // Version 3 nests everything in a "Schematic" compound
+        let schematic = match root.get("Schematic") {
+            Some(nbt::Tag::Compound(inner)) => inner,
+            _ => &root,
+        };
+
+        let dimension = |name: &str| match schematic.get(name) {
+            Some(nbt::Tag::Short(value)) => Ok(*value as u16 as usize),
+            _ => Err(SchematicError::new(format!("Missing {name}"))),
+        };
+        let (width, height, length) = (
+            dimension("Width")?,
+            dimension("Height")?,
+            dimension("Length")?,
+        );
+
+        let (palette, block_data) = match schematic.get("Blocks") {
+            Some(nbt::Tag::Compound(blocks)) => (blocks.get("Palette"), blocks.get("Data")),
+            _ => (schematic.get("Palette"), schematic.get("BlockData")),
+        };
+
+        let Some(nbt::Tag::Compound(palette)) = palette else {
+            return Err(SchematicError::new("Missing block palette".to_string()));
+        };
+        let Some(nbt::Tag::ByteArray(block_data)) = block_data else {
+            return Err(SchematicError::new("Missing block data".to_string()));
+        };
+
+        let mut mapping = HashMap::new();
+        for (state, index) in palette.iter() {
+            let nbt::Tag::Int(index) = index else {
+                return Err(SchematicError::new(format!(
+                    "Palette entry \"{state}\" isn't an int"
+                )));
+            };
+
+            let voxel = sponge_block(state).unwrap_or_else(|| {
+                log::warn!("No block matches \"{state}\", importing it as air");
+                Blocks::AIR.default_state()
+            });
+            mapping.insert(*index, voxel);
+        }
+
+        // Every voxel takes at least a byte, checked before allocating anything of that size
+        let count = [width, height, length]
+            .iter()
+            .try_fold(1usize, |count, side| count.checked_mul(*side))
+            .ok_or_else(|| SchematicError::new("Schematic is too large".to_string()))?;
+        if count > block_data.len() {
+            return Err(SchematicError::new("Block data is too short".to_string()));
+        }
+
+        let mut structure = Structure::new([width, height, length]);
+        let mut bytes = block_data.iter();
+
+        // Sponge orders voxels X first, then Z, then Y
+        for y in 0..height {
+            for z in 0..length {
+                for x in 0..width {
+                    let index = read_varint(&mut bytes).ok_or_else(|| {
+                        SchematicError::new("Block data is too short".to_string())
+                    })?;
+                    let voxel = mapping.get(&index).copied().ok_or_else(|| {
+                        SchematicError::new(format!("Palette index {index} out of range"))
+                    })?;
+
+                    structure.set(x, y, z, voxel);
+                }
+            }
+        }
+
+        let mut metadata = BTreeMap::new();
+        if let Some(nbt::Tag::Compound(entries)) = schematic.get("Metadata") {
+            for (key, value) in entries.iter() {
+                if let nbt::Tag::String(value) = value {
+                    metadata.insert(key.clone(), value.clone());
+                }
+            }
+        }
+
+        Ok(Self {
+            structure,
+            metadata,
+        })
+    }
+}
+
+/// Block states without a block of the same name
+const SPONGE_ALIASES: &[(&str, &str)] = &[
+    ("cave_air", "air"),
+    ("void_air", "air"),
+    ("grass", "air"),
+    ("short_grass", "air"),
+    ("tall_grass", "air"),
+    ("water", "air"),
+    ("cobblestone", "stone"),
+    ("stone_bricks", "stone"),
+    ("andesite", "stone"),
+    ("diorite", "stone"),
+    ("granite", "stone"),
+    ("deepslate", "stone"),
+    ("bedrock", "stone"),
+    ("gravel", "stone"),
+    ("coarse_dirt", "dirt"),
+    ("rooted_dirt", "dirt"),
+    ("podzol", "grass_block"),
+    ("mycelium", "grass_block"),
+    ("red_sand", "sand"),
+    ("sandstone", "sand"),
+];
+
+/// Maps a Sponge block state like `minecraft:oak_log[axis=y]` to a block
+fn sponge_block(state: &str) -> Option<Voxel> {
+    let name = state.split('[').next().unwrap_or(state);
+    let name = name.rsplit(':').next().unwrap_or(name);
+
+    let name = SPONGE_ALIASES
+        .iter()
+        .find(|(from, _)| *from == name)
+        .map(|(_, to)| *to)
+        .unwrap_or(name);
+
+    if let Some(block) = Blocks::by_name(name) {
+        return Some(block.default_state());
+    }
+
+    if name.ends_with("_log") || name.ends_with("_wood") || name.ends_with("_stem") {
+        return Some(Blocks::LOG.default_state());
+    }
+
+    None
+}
+
+fn read_varint<'a>(bytes: &mut impl Iterator<Item = &'a i8>) -> Option<i32> {
+    let mut value = 0;
+
+    for shift in (0..35).step_by(7) {
+        let byte = *bytes.next()? as u8;
+        value |= ((byte & 0x7f) as i32) << shift;
+
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+/// Smallest number of bits that can hold every palette index, at least one
+fn bits_per_entry(palette_len: usize) -> usize {
+    (usize::BITS - palette_len.saturating_sub(1).leading_zeros()).max(1) as usize
+}
+
+fn pack(indices: &[u64], bits: usize) -> Vec<u64> {
+    let per_word = 64 / bits;
+    let mut words = vec![0; indices.len().div_ceil(per_word)];
+
+    for (i, index) in indices.iter().enumerate() {
+        words[i / per_word] |= index << ((i % per_word) * bits);
+    }
+
+    words
+}
+
+fn unpack(words: &[u64], bits: usize, count: usize) -> Option<Vec<u64>> {
+    if bits == 0 || bits > 32 {
+        return None;
+    }
+
+    let per_word = 64 / bits;
+    let mask = (1 << bits) - 1;
+
+    if count.div_ceil(per_word) > words.len() {
+        return None;
+    }
+
+    (0..count)
+        .map(|i| {
+            words
+                .get(i / per_word)
+                .map(|word| (word >> ((i % per_word) * bits)) & mask)
+        })
+        .collect()
+}
+
+fn write_string(data: &mut Vec<u8>, string: &str) {
+    data.extend_from_slice(&(string.len() as u16).to_le_bytes());
+    data.extend_from_slice(string.as_bytes());
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn take(&mut self, count: usize) -> SchematicResult<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| SchematicError::new("Unexpected end of file".to_string()))?;
+
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> SchematicResult<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    /// Reads a little endian u16 length followed by UTF-8
+    fn string(&mut self) -> SchematicResult<String> {
+        let len = u16::from_le_bytes(self.array()?) as usize;
+
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|e| SchematicError::new(format!("Invalid string: {e}")))
+    }
+}
+
+/// Just enough of Minecraft's NBT format to read Sponge schematics
+mod nbt {
+    use std::collections::HashMap;
+
+    use super::{ByteReader, SchematicError, SchematicResult};
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    pub enum Tag {
+        Byte(i8),
+        Short(i16),
+        Int(i32),
+        Long(i64),
+        Float(f32),
+        Double(f64),
+        ByteArray(Vec<i8>),
+        String(String),
+        List(Vec<Tag>),
+        Compound(HashMap<String, Tag>),
+        IntArray(Vec<i32>),
+        LongArray(Vec<i64>),
+    }
+
+    /// Reads the named root tag, discarding its name
+    pub fn read_root(reader: &mut ByteReader) -> SchematicResult<Tag> {
+        let id = reader.array::<1>()?[0];
+        read_string(reader)?;
+
+        read_payload(reader, id, 0)
+    }
+
+    fn read_string(reader: &mut ByteReader) -> SchematicResult<String> {
+        let len = u16::from_be_bytes(reader.array()?) as usize;
+
+        // Modified UTF-8 matches plain UTF-8 for everything found in block names
+        Ok(String::from_utf8_lossy(reader.take(len)?).into_owned())
+    }
+
+    fn read_len(reader: &mut ByteReader) -> SchematicResult<usize> {
+        Ok(i32::from_be_bytes(reader.array()?).max(0) as usize)
+    }
+
+    fn read_payload(reader: &mut ByteReader, id: u8, depth: usize) -> SchematicResult<Tag> {
+        const MAX_DEPTH: usize = 512;
+
+        if depth > MAX_DEPTH {
+            return Err(SchematicError::new("NBT nested too deep".to_string()));
+        }
+
+        Ok(match id {
+            1 => Tag::Byte(i8::from_be_bytes(reader.array()?)),
+            2 => Tag::Short(i16::from_be_bytes(reader.array()?)),
+            3 => Tag::Int(i32::from_be_bytes(reader.array()?)),
+            4 => Tag::Long(i64::from_be_bytes(reader.array()?)),
+            5 => Tag::Float(f32::from_be_bytes(reader.array()?)),
+            6 => Tag::Double(f64::from_be_bytes(reader.array()?)),
+            7 => {
+                let len = read_len(reader)?;
+                Tag::ByteArray(reader.take(len)?.iter().map(|b| *b as i8).collect())
+            }
+            8 => Tag::String(read_string(reader)?),
+            9 => {
+                let element = reader.array::<1>()?[0];
+                let len = read_len(reader)?;
+
+                let mut list = Vec::new();
+                for _ in 0..len {
+                    list.push(read_payload(reader, element, depth + 1)?);
+                }
+                Tag::List(list)
+            }
+            10 => {
+                let mut compound = HashMap::new();
+
+                loop {
+                    let id = reader.array::<1>()?[0];
+                    if id == 0 {
+                        break;
+                    }
+
+                    let name = read_string(reader)?;
+                    compound.insert(name, read_payload(reader, id, depth + 1)?);
+                }
+                Tag::Compound(compound)
+            }
+            11 => {
+                let len = read_len(reader)?;
+                let bytes = reader.take(len.saturating_mul(4))?;
+                Tag::IntArray(
+                    bytes
+                        .chunks_exact(4)
+                        .map(|b| i32::from_be_bytes(b.try_into().unwrap()))
+                        .collect(),
+                )
+            }
+            12 => {
+                let len = read_len(reader)?;
+                let bytes = reader.take(len.saturating_mul(8))?;
+                Tag::LongArray(
+                    bytes
+                        .chunks_exact(8)
+                        .map(|b| i64::from_be_bytes(b.try_into().unwrap()))
+                        .collect(),
+                )
+            }
+            id => return Err(SchematicError::new(format!("Unknown NBT tag {id}"))),
+        })
+    }
+}
@@ -0,0 +1,131 @@
+use super::{
+    chunk::WorldCoord,
+    flat::{DebugGenerator, SuperflatGenerator},
+    heightmap::HeightmapGenerator,
+    Generator, NoiseGenerator,
+};
+
+/// Generator picked by name, for choosing one at startup
+pub enum GeneratorSpec {
+    Noise,
+    /// Layers bottom to top, `SuperflatGenerator::parse_layers` syntax
+    Superflat(Option<String>),
+    Debug,
+    Heightmap {
+        image: String,
+        colors: Option<String>,
+        scale: Option<(f32, f32)>,
+        origin: Option<WorldCoord>,
+    },
+}
+
+#[allow(dead_code)]
+impl GeneratorSpec {
+    /// Parses one of:
+    ///
+    /// ```text
+    /// noise
+    /// flat[:<layers>]                 flat:stone*60,dirt*3,grass_block
+    /// debug
+    /// heightmap:<image>[;<option>]    heightmap:map.png;colors=map_colors.png;scale=2,128;origin=0,-20,0
+    /// ```
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (name, arguments) = match spec.split_once(':') {
+            Some((name, arguments)) => (name, Some(arguments)),
+            None => (spec, None),
+        };
+
+        match (name.trim(), arguments) {
+            ("noise", None) => Ok(Self::Noise),
+            ("flat", layers) => Ok(Self::Superflat(layers.map(str::to_string))),
+            ("debug", None) => Ok(Self::Debug),
+            ("heightmap", Some(arguments)) => Self::parse_heightmap(arguments),
+            ("heightmap", None) => Err("Heightmap generator needs an image".to_string()),
+            (name, Some(_)) if ["noise", "debug"].contains(&name) => {
+                Err(format!("Generator \"{name}\" takes no arguments"))
+            }
+            (name, _) => Err(format!("Unknown generator \"{name}\"")),
+        }
+    }
+
+    fn parse_heightmap(arguments: &str) -> Result<Self, String> {
+        let mut options = arguments.split(';');
+        let image = options.next().unwrap_or_default().to_string();
+
+        let numbers = |value: &str| -> Result<Vec<f32>, String> {
+            value
+                .split(',')
+                .map(|n| {
+                    n.trim()
+                        .parse()
+                        .map_err(|_| format!("Invalid number \"{n}\""))
+                })
+                .collect()
+        };
+
+        let mut colors = None;
+        let mut scale = None;
+        let mut origin = None;
+
+        for option in options {
+            let (key, value) = option
+                .split_once('=')
+                .ok_or_else(|| format!("Expected key=value, got \"{option}\""))?;
+
+            match (key.trim(), numbers(value).as_deref()) {
+                ("colors", _) => colors = Some(value.to_string()),
+                ("scale", Ok(&[horizontal, vertical])) => scale = Some((horizontal, vertical)),
+                ("origin", Ok(&[x, y, z])) => {
+                    origin = Some(WorldCoord {
+                        x: x as i32,
+                        y: y as i32,
+                        z: z as i32,
+                    })
+                }
+                (key, _) => return Err(format!("Invalid heightmap option \"{key}={value}\"")),
+            }
+        }
+
+        Ok(Self::Heightmap {
+            image,
+            colors,
+            scale,
+            origin,
+        })
+    }
+
+    /// Creates the generator, loading any files it needs
+    pub fn build(&self, seed: i32) -> Result<Box<dyn Generator>, String> {
+        Ok(match self {
+            Self::Noise => Box::new(NoiseGenerator::new(seed)),
+            Self::Superflat(None) => Box::new(SuperflatGenerator::default()),
+            Self::Superflat(Some(layers)) => Box::new(SuperflatGenerator::new(
+                SuperflatGenerator::parse_layers(layers)?,
+            )),
+            Self::Debug => Box::new(DebugGenerator::default()),
+            Self::Heightmap {
+                image,
+                colors,
+                scale,
+                origin,
+            } => {
+                let mut generator =
+                    HeightmapGenerator::from_file(image).map_err(|e| e.to_string())?;
+
+                if let Some(colors) = colors {
+                    generator = generator
+                        .with_color_file(colors)
+                        .map_err(|e| e.to_string())?;
+                }
+                if let Some((horizontal, vertical)) = scale {
+                    generator = generator.with_scale(*horizontal, *vertical);
+                }
+                if let Some(origin) = origin {
+                    generator = generator.with_origin(*origin);
+                }
+
+                Box::new(generator)
+            }
+        })
+    }
+}
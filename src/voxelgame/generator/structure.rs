@@ -0,0 +1,144 @@
+use super::{
+    chunk::{BlockOffsetCoord, WorldCoord},
+    edit::WorldEdit,
+    voxel::{Blocks, Voxel},
+    World,
+};
+
+/// Box of voxels detached from the world, stored X first, then Y, then Z
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Structure {
+    size: [usize; 3],
+    voxels: Vec<Voxel>,
+}
+
+#[allow(dead_code)]
+impl Structure {
+    /// Creates a structure filled with air
+    pub fn new(size: [usize; 3]) -> Self {
+        Self {
+            size,
+            voxels: vec![Blocks::AIR.default_state(); size[0] * size[1] * size[2]],
+        }
+    }
+
+    pub fn size(&self) -> [usize; 3] {
+        self.size
+    }
+
+    pub fn voxels(&self) -> &[Voxel] {
+        &self.voxels
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        x + y * self.size[0] + z * self.size[0] * self.size[1]
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<Voxel> {
+        if x >= self.size[0] || y >= self.size[1] || z >= self.size[2] {
+            return None;
+        }
+
+        Some(self.voxels[self.index(x, y, z)])
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, voxel: Voxel) {
+        if x >= self.size[0] || y >= self.size[1] || z >= self.size[2] {
+            return;
+        }
+
+        let index = self.index(x, y, z);
+        self.voxels[index] = voxel;
+    }
+
+    /// Iterates over every voxel with its position inside the structure
+    pub fn iter(&self) -> impl Iterator<Item = ([usize; 3], Voxel)> + '_ {
+        let [sx, sy, _] = self.size;
+
+        self.voxels
+            .iter()
+            .enumerate()
+            .map(move |(i, voxel)| ([i % sx, (i / sx) % sy, i / (sx * sy)], *voxel))
+    }
+
+    /// Rotates by 90 degrees clockwise around the Y axis (looking down)
+    pub fn rotated_y(&self) -> Self {
+        let [sx, sy, sz] = self.size;
+        let mut rotated = Self::new([sz, sy, sx]);
+
+        for ([x, y, z], voxel) in self.iter() {
+            rotated.set(sz - 1 - z, y, x, voxel);
+        }
+
+        rotated
+    }
+
+    /// Mirrors along the X axis
+    pub fn mirrored_x(&self) -> Self {
+        let mut mirrored = Self::new(self.size);
+
+        for ([x, y, z], voxel) in self.iter() {
+            mirrored.set(self.size[0] - 1 - x, y, z, voxel);
+        }
+
+        mirrored
+    }
+
+    /// Mirrors along the Z axis
+    pub fn mirrored_z(&self) -> Self {
+        let mut mirrored = Self::new(self.size);
+
+        for ([x, y, z], voxel) in self.iter() {
+            mirrored.set(x, y, self.size[2] - 1 - z, voxel);
+        }
+
+        mirrored
+    }
+
+    /// Edit placing the structure with its minimum corner at `origin`.
+    /// Air is only written when `include_air` is set.
+    pub fn paste_edit(&self, origin: WorldCoord, include_air: bool) -> WorldEdit {
+        let mut edit = WorldEdit::new();
+
+        for ([x, y, z], voxel) in self.iter() {
+            if !include_air && voxel == Blocks::AIR.default_state() {
+                continue;
+            }
+
+            let offset = BlockOffsetCoord {
+                x: x as i32,
+                y: y as i32,
+                z: z as i32,
+            };
+            edit.set(origin + offset, voxel);
+        }
+
+        edit
+    }
+}
+
+#[allow(dead_code)]
+impl<T> World<T> {
+    /// Copies the inclusive region between `min` and `max` into a structure.
+    /// Voxels in unloaded chunks are copied as air.
+    pub fn copy_region(&self, min: WorldCoord, max: WorldCoord) -> Structure {
+        let size = max - min;
+        let mut structure = Structure::new([
+            size.x as usize + 1,
+            size.y as usize + 1,
+            size.z as usize + 1,
+        ]);
+
+        self.for_each_voxel_in_region(min, max, |coord, voxel| {
+            let offset = coord - min;
+            structure.set(
+                offset.x as usize,
+                offset.y as usize,
+                offset.z as usize,
+                voxel,
+            );
+        });
+
+        structure
+    }
+}
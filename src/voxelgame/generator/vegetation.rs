@@ -0,0 +1,422 @@
+use std::ops::RangeInclusive;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    chunk::{Chunk, ChunkLocalCoord, WorldCoord, CHUNK_SIZE},
+    feature::{hash_cell, Biome, Feature, Terrain},
+    voxel::{Blocks, Voxel},
+};
+
+/// Where a plant may grow
+#[derive(Clone, Debug)]
+pub struct PlacementRule {
+    /// Blocks it can stand on
+    pub surfaces: Vec<Voxel>,
+    /// Biomes it grows in with the chance of it growing on a column there
+    pub chances: Vec<(Biome, f32)>,
+}
+
+impl PlacementRule {
+    pub fn new(surfaces: &[Voxel], chances: &[(Biome, f32)]) -> Self {
+        Self {
+            surfaces: surfaces.to_vec(),
+            chances: chances.to_vec(),
+        }
+    }
+
+    /// Chance of growing on a column with this biome and surface block
+    pub fn chance(&self, biome: Biome, surface: Voxel) -> f32 {
+        if !self.surfaces.contains(&surface) {
+            return 0.0;
+        }
+
+        self.chances
+            .iter()
+            .find(|(b, _)| *b == biome)
+            .map(|(_, chance)| *chance)
+            .unwrap_or(0.0)
+    }
+
+    fn max_chance(&self) -> f32 {
+        self.chances
+            .iter()
+            .map(|(_, chance)| *chance)
+            .fold(0.0, f32::max)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Canopy {
+    None,
+    /// Ball of leaves around the top of the trunk
+    Round {
+        radius: u32,
+    },
+    /// Layers of leaves narrowing towards the top
+    Cone {
+        radius: u32,
+        height: u32,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct TreeShape {
+    pub trunk_height: RangeInclusive<u32>,
+    /// Branches growing out of the upper half of the trunk
+    pub branches: RangeInclusive<u32>,
+    pub branch_length: u32,
+    pub canopy: Canopy,
+    pub log: Voxel,
+    pub leaves: Voxel,
+}
+
+impl TreeShape {
+    /// Furthest blocks reach from the trunk sideways and above the ground
+    fn reach(&self) -> (i32, i32) {
+        let (radius, above) = match self.canopy {
+            Canopy::None => (0, 0),
+            Canopy::Round { radius } => (radius, radius),
+            Canopy::Cone { radius, .. } => (radius, 1),
+        };
+        // Leaves around the branch tips
+        let branches = self.branch_length + 1;
+
+        (
+            radius.max(branches) as i32,
+            (*self.trunk_height.end() + above.max(branches)) as i32,
+        )
+    }
+
+    fn blocks(&self, rng: &mut StdRng, blocks: &mut Vec<([i32; 3], Voxel)>) {
+        let height = rng.random_range(self.trunk_height.clone()) as i32;
+        let top = height - 1;
+
+        match self.canopy {
+            Canopy::None => {}
+            Canopy::Round { radius } => {
+                leaf_ball(rng, [0, top, 0], radius as i32, self.leaves, blocks);
+            }
+            Canopy::Cone {
+                radius,
+                height: cone,
+            } => {
+                let cone = cone as i32;
+
+                for layer in 0..=cone {
+                    let y = top + 1 - layer;
+                    // Every other layer is smaller, giving the cone a jagged edge
+                    let width = (radius as i32 * layer / cone.max(1)) - (layer % 2);
+
+                    for x in -width..=width {
+                        for z in -width..=width {
+                            if x.abs() + z.abs() <= width + width / 2 {
+                                blocks.push(([x, y, z], self.leaves));
+                            }
+                        }
+                    }
+                }
+                blocks.push(([0, top + 1, 0], self.leaves));
+            }
+        }
+
+        for _ in 0..rng.random_range(self.branches.clone()) {
+            let (dx, dz) = [
+                (1, 0),
+                (-1, 0),
+                (0, 1),
+                (0, -1),
+                (1, 1),
+                (-1, -1),
+                (1, -1),
+                (-1, 1),
+            ][rng.random_range(0..8)];
+            let mut position = [
+                0,
+                rng.random_range(height / 2..height.max(height / 2 + 1)),
+                0,
+            ];
+
+            for step in 0..self.branch_length as i32 {
+                position[0] += dx;
+                position[2] += dz;
+                // Branches rise every other block
+                position[1] += step % 2;
+
+                blocks.push((position, self.log));
+            }
+
+            if !matches!(self.canopy, Canopy::None) {
+                leaf_ball(rng, position, 1, self.leaves, blocks);
+            }
+        }
+
+        for y in 0..height {
+            blocks.push(([0, y, 0], self.log));
+        }
+    }
+}
+
+/// Roughly round ball of leaves with some corners left out
+fn leaf_ball(
+    rng: &mut StdRng,
+    center: [i32; 3],
+    radius: i32,
+    leaves: Voxel,
+    blocks: &mut Vec<([i32; 3], Voxel)>,
+) {
+    for x in -radius..=radius {
+        for y in -radius..=radius {
+            for z in -radius..=radius {
+                let distance = ((x * x + y * y + z * z) as f32).sqrt();
+
+                if distance > radius as f32 + 0.5
+                    || (distance > radius as f32 - 0.5 && rng.random_bool(0.5))
+                {
+                    continue;
+                }
+
+                blocks.push(([center[0] + x, center[1] + y, center[2] + z], leaves));
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Plant {
+    Tree(TreeShape),
+    /// Leaves around a single log on the ground
+    Bush {
+        radius: u32,
+    },
+    /// Single block such as grass or a flower
+    Single(Voxel),
+}
+
+/// Scatters plants over the terrain.
+///
+/// Every column rolls its own chance of growing one, so plants don't line up.
+pub struct VegetationFeature {
+    plant: Plant,
+    rule: PlacementRule,
+    /// Multiplies the chances of the rule
+    density: f32,
+    salt: u64,
+}
+
+#[allow(dead_code)]
+impl VegetationFeature {
+    pub fn new(plant: Plant, rule: PlacementRule, salt: u64) -> Self {
+        Self {
+            plant,
+            rule,
+            density: 1.0,
+            salt,
+        }
+    }
+
+    pub fn with_density(mut self, density: f32) -> Self {
+        self.density = density.max(0.0);
+        self
+    }
+
+    /// Trees, bushes, grass and flowers of every biome
+    pub fn library() -> Vec<Self> {
+        use Biome::*;
+
+        let grass = Blocks::GRASS_BLOCK.default_state();
+        let dirt = Blocks::DIRT_BLOCK.default_state();
+        let sand = Blocks::SAND.default_state();
+        let log = Blocks::LOG.default_state();
+        let leaves = Blocks::LEAVES.default_state();
+
+        let plants = [
+            (
+                Plant::Tree(TreeShape {
+                    trunk_height: 4..=6,
+                    branches: 0..=1,
+                    branch_length: 2,
+                    canopy: Canopy::Round { radius: 2 },
+                    log,
+                    leaves,
+                }),
+                PlacementRule::new(&[grass, dirt], &[(Plains, 0.002), (Forest, 0.025)]),
+            ),
+            (
+                Plant::Tree(TreeShape {
+                    trunk_height: 7..=10,
+                    branches: 2..=4,
+                    branch_length: 3,
+                    canopy: Canopy::Round { radius: 3 },
+                    log,
+                    leaves,
+                }),
+                PlacementRule::new(&[grass, dirt], &[(Forest, 0.004)]),
+            ),
+            (
+                Plant::Tree(TreeShape {
+                    trunk_height: 6..=9,
+                    branches: 0..=0,
+                    branch_length: 0,
+                    canopy: Canopy::Cone {
+                        radius: 3,
+                        height: 6,
+                    },
+                    log,
+                    leaves,
+                }),
+                PlacementRule::new(&[grass, dirt], &[(Forest, 0.01)]),
+            ),
+            (
+                Plant::Tree(TreeShape {
+                    trunk_height: 3..=5,
+                    branches: 1..=3,
+                    branch_length: 2,
+                    canopy: Canopy::None,
+                    log,
+                    leaves,
+                }),
+                PlacementRule::new(&[sand], &[(Desert, 0.0005)]),
+            ),
+            (
+                Plant::Bush { radius: 1 },
+                PlacementRule::new(&[grass, dirt], &[(Plains, 0.004), (Forest, 0.01)]),
+            ),
+            (
+                Plant::Single(Blocks::TALL_GRASS.default_state()),
+                PlacementRule::new(&[grass], &[(Plains, 0.15), (Forest, 0.08)]),
+            ),
+            (
+                Plant::Single(Blocks::POPPY.default_state()),
+                PlacementRule::new(&[grass], &[(Plains, 0.01)]),
+            ),
+            (
+                Plant::Single(Blocks::DANDELION.default_state()),
+                PlacementRule::new(&[grass], &[(Plains, 0.01), (Forest, 0.004)]),
+            ),
+            (
+                Plant::Single(Blocks::DEAD_BUSH.default_state()),
+                PlacementRule::new(&[sand], &[(Desert, 0.008)]),
+            ),
+        ];
+
+        plants
+            .into_iter()
+            .enumerate()
+            .map(|(i, (plant, rule))| Self::new(plant, rule, 0x7665_6700 + i as u64))
+            .collect()
+    }
+
+    /// Furthest blocks reach from the base sideways and above it
+    fn reach(&self) -> (i32, i32) {
+        match &self.plant {
+            Plant::Tree(shape) => shape.reach(),
+            Plant::Bush { radius } => (*radius as i32, *radius as i32),
+            Plant::Single(_) => (0, 0),
+        }
+    }
+
+    /// Blocks of one plant relative to the block above the ground
+    pub fn blocks(&self, rng: &mut StdRng) -> Vec<([i32; 3], Voxel)> {
+        let mut blocks = Vec::new();
+
+        match &self.plant {
+            Plant::Tree(shape) => shape.blocks(rng, &mut blocks),
+            Plant::Bush { radius } => {
+                let leaves = Blocks::LEAVES.default_state();
+                leaf_ball(rng, [0, 0, 0], *radius as i32, leaves, &mut blocks);
+                blocks.push(([0, 0, 0], Blocks::LOG.default_state()));
+            }
+            Plant::Single(voxel) => blocks.push(([0, 0, 0], *voxel)),
+        }
+
+        blocks
+    }
+}
+
+/// Plants only grow into air, except for opaque parts like trunks which
+/// also push through leaves and other plants
+fn can_replace(current: Voxel, new: Voxel) -> bool {
+    let current = Blocks::BLOCKS[current.id as usize];
+    let new = Blocks::BLOCKS[new.id as usize];
+
+    current.default_state() == Blocks::AIR.default_state()
+        || (current.transparent && !new.transparent)
+}
+
+impl Feature for VegetationFeature {
+    fn place(&self, chunk: &mut Chunk, seed: i32, terrain: &dyn Terrain) {
+        let max_chance = self.rule.max_chance() * self.density;
+        if max_chance <= 0.0 {
+            return;
+        }
+
+        let chunk_min: WorldCoord = chunk.coord.into();
+        let size = CHUNK_SIZE as i32;
+        let (radius, height) = self.reach();
+
+        for x in chunk_min.x - radius..chunk_min.x + size + radius {
+            for z in chunk_min.z - radius..chunk_min.z + size + radius {
+                let hash = hash_cell(seed, self.salt, x, z);
+                let roll = (hash >> 40) as f32 / (1u64 << 24) as f32;
+
+                if roll >= max_chance {
+                    continue;
+                }
+
+                let base = terrain.surface_height(x, z) + 1;
+                if base + height < chunk_min.y || base >= chunk_min.y + size {
+                    continue;
+                }
+
+                let chance = self
+                    .rule
+                    .chance(terrain.biome(x, z), terrain.surface_block(x, z));
+                if roll >= chance * self.density {
+                    continue;
+                }
+
+                // Single blocks can check what they stand on, unlike plants
+                // spanning chunks which have to look the same from every chunk
+                let local_base = [x - chunk_min.x, base - chunk_min.y, z - chunk_min.z];
+                if let Plant::Single(_) = self.plant {
+                    let below = chunk.get_voxel(ChunkLocalCoord {
+                        x: local_base[0] as usize,
+                        y: (local_base[1] - 1).max(0) as usize,
+                        z: local_base[2] as usize,
+                    });
+
+                    if local_base[1] > 0 && below == Some(Blocks::AIR.default_state()) {
+                        continue;
+                    }
+                }
+
+                let mut rng = StdRng::seed_from_u64(hash);
+                for (offset, voxel) in self.blocks(&mut rng) {
+                    let local = [
+                        local_base[0] + offset[0],
+                        local_base[1] + offset[1],
+                        local_base[2] + offset[2],
+                    ];
+
+                    if local.iter().any(|v| !(0..size).contains(v)) {
+                        continue;
+                    }
+
+                    let coord = ChunkLocalCoord {
+                        x: local[0] as usize,
+                        y: local[1] as usize,
+                        z: local[2] as usize,
+                    };
+
+                    if chunk
+                        .get_voxel(coord)
+                        .is_some_and(|current| can_replace(current, voxel))
+                    {
+                        chunk.set_voxel(coord, voxel);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::{HashSet, VecDeque};
+
+use cgmath::{Array, Vector3};
+
+use super::{
+    chunk::{Chunk, ChunkCoord, CHUNK_SIZE},
+    voxel::{BlockModel, Blocks, Voxel},
+};
+
+/// Offset to the neighbouring chunk through each face, in the order faces
+/// are meshed in: left, right, top, bottom, back and front.
+/// Opposite faces only differ in the lowest bit.
+pub const FACE_OFFSETS: [[i32; 3]; 6] = [
+    [-1, 0, 0],
+    [1, 0, 0],
+    [0, 1, 0],
+    [0, -1, 0],
+    [0, 0, 1],
+    [0, 0, -1],
+];
+
+pub const fn opposite_face(face: usize) -> usize {
+    face ^ 1
+}
+
+/// Which directions faces of the chunk at `coord` can point in and still be seen from `eye`,
+/// in `FACE_OFFSETS` order.
+///
+/// A face is only seen from in front of it, and faces inside the chunk lie
+/// between its sides, so e.g. right faces can't be seen from left of the chunk.
+pub fn facing_faces(coord: ChunkCoord, eye: Vector3<f32>) -> [bool; 6] {
+    let min: Vector3<f32> = coord.into();
+    let max = min + Vector3::from_value(CHUNK_SIZE as f32);
+
+    FACE_OFFSETS.map(|offset| {
+        let axis = offset.iter().position(|d| *d != 0).unwrap();
+
+        if offset[axis] > 0 {
+            eye[axis] > min[axis]
+        } else {
+            eye[axis] < max[axis]
+        }
+    })
+}
+
+/// Whether the camera can see through a voxel, anything but a full opaque cube
+fn is_open(voxel: Voxel) -> bool {
+    let info = Blocks::BLOCKS[voxel.id as usize];
+
+    info.transparent || info.model != BlockModel::Cube
+}
+
+/// Which pairs of a chunk's faces are connected through open voxels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkConnectivity {
+    /// Bit `a * 6 + b` is set when face `a` connects to face `b`
+    bits: u64,
+}
+
+#[allow(dead_code)]
+impl ChunkConnectivity {
+    pub const NONE: Self = Self { bits: 0 };
+    pub const ALL: Self = Self {
+        bits: (1 << 36) - 1,
+    };
+
+    pub fn connects(&self, a: usize, b: usize) -> bool {
+        self.bits & (1 << (a * 6 + b)) != 0
+    }
+
+    /// Connects every pair of the faces in `faces`, a bit per face
+    fn connect_all(&mut self, faces: u8) {
+        for a in 0..6 {
+            for b in 0..6 {
+                if faces & (1 << a) != 0 && faces & (1 << b) != 0 {
+                    self.bits |= 1 << (a * 6 + b);
+                }
+            }
+        }
+    }
+
+    /// Flood fills the open voxels of `chunk`, connecting the faces each region touches
+    pub fn compute(chunk: &Chunk) -> Self {
+        const SIZE: usize = CHUNK_SIZE;
+        let index = |x: usize, y: usize, z: usize| x + y * SIZE + z * SIZE * SIZE;
+
+        let mut connectivity = Self::NONE;
+        let mut visited = vec![false; SIZE * SIZE * SIZE];
+        let mut stack = Vec::new();
+
+        for start in 0..visited.len() {
+            if visited[start] || !is_open(chunk.chunk_data[start]) {
+                continue;
+            }
+
+            let mut faces = 0u8;
+            visited[start] = true;
+            stack.push(start);
+
+            while let Some(current) = stack.pop() {
+                let (x, y, z) = (
+                    current % SIZE,
+                    current / SIZE % SIZE,
+                    current / (SIZE * SIZE),
+                );
+
+                let on_face = [
+                    x == 0,
+                    x == SIZE - 1,
+                    y == SIZE - 1,
+                    y == 0,
+                    z == SIZE - 1,
+                    z == 0,
+                ];
+                for (face, on_face) in on_face.into_iter().enumerate() {
+                    if on_face {
+                        faces |= 1 << face;
+                    }
+                }
+
+                for [dx, dy, dz] in FACE_OFFSETS {
+                    let (nx, ny, nz) = (x as i32 + dx, y as i32 + dy, z as i32 + dz);
+                    if [nx, ny, nz].iter().any(|n| !(0..SIZE as i32).contains(n)) {
+                        continue;
+                    }
+
+                    let neighbor = index(nx as usize, ny as usize, nz as usize);
+                    if !visited[neighbor] && is_open(chunk.chunk_data[neighbor]) {
+                        visited[neighbor] = true;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+
+            connectivity.connect_all(faces);
+            if connectivity == Self::ALL {
+                break;
+            }
+        }
+
+        connectivity
+    }
+}
+
+/// Chunks the camera in `start` can see into through connected faces,
+/// at most `max_distance` chunks away on each axis.
+///
+/// The search never turns back against a direction it already went in.
+/// `connectivity` gives `None` for chunks that aren't meshed yet, which are treated as open.
+pub fn visible_chunks(
+    start: ChunkCoord,
+    max_distance: i32,
+    connectivity: impl Fn(ChunkCoord) -> Option<ChunkConnectivity>,
+) -> HashSet<ChunkCoord> {
+    let mut visited = HashSet::from([start]);
+    // Chunk, the face it was entered through and the directions taken to get there
+    let mut queue = VecDeque::from([(start, None::<usize>, 0u8)]);
+
+    while let Some((coord, entered, directions)) = queue.pop_front() {
+        let faces = connectivity(coord).unwrap_or(ChunkConnectivity::ALL);
+
+        for (face, [dx, dy, dz]) in FACE_OFFSETS.into_iter().enumerate() {
+            if directions & (1 << opposite_face(face)) != 0 {
+                continue;
+            }
+            if entered.is_some_and(|entered| !faces.connects(entered, face)) {
+                continue;
+            }
+
+            let neighbor = ChunkCoord {
+                x: coord.x + dx,
+                y: coord.y + dy,
+                z: coord.z + dz,
+            };
+            let distance = [
+                neighbor.x - start.x,
+                neighbor.y - start.y,
+                neighbor.z - start.z,
+            ];
+            if distance.iter().any(|d| d.abs() > max_distance) || !visited.insert(neighbor) {
+                continue;
+            }
+
+            queue.push_back((neighbor, Some(opposite_face(face)), directions | 1 << face));
+        }
+    }
+
+    visited
+}
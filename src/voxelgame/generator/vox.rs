@@ -0,0 +1,294 @@
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+use super::{
+    structure::Structure,
+    voxel::{Blocks, Voxel},
+};
+
+#[derive(Debug, Clone)]
+pub struct VoxError {
+    pub message: String,
+}
+
+impl VoxError {
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl Display for VoxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for VoxError {}
+type VoxResult<T> = Result<T, VoxError>;
+
+/// Blocks picked for MagicaVoxel palette indices instead of the nearest color
+#[derive(Clone, Debug, Default)]
+pub struct VoxMapping {
+    blocks: HashMap<u8, Voxel>,
+}
+
+#[allow(dead_code)]
+impl VoxMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, index: u8, voxel: Voxel) {
+        self.blocks.insert(index, voxel);
+    }
+
+    pub fn get(&self, index: u8) -> Option<Voxel> {
+        self.blocks.get(&index).copied()
+    }
+
+    /// Parses lines like `12 = Grass Block`, `#` starts a comment
+    pub fn parse(text: &str) -> VoxResult<Self> {
+        let mut mapping = Self::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = |message: &str| VoxError::new(format!("Line {}: {message}", number + 1));
+
+            let (index, name) = line.split_once('=').ok_or_else(|| error("expected `=`"))?;
+            let index: u8 = index
+                .trim()
+                .parse()
+                .map_err(|_| error("palette index must be between 0 and 255"))?;
+            let block = Blocks::by_name(name).ok_or_else(|| error("unknown block"))?;
+
+            mapping.set(index, block.default_state());
+        }
+
+        Ok(mapping)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> VoxResult<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| VoxError::new(format!("Failed to read mapping: {e}")))?;
+
+        Self::parse(&text)
+    }
+}
+
+/// Reads the first model of a MagicaVoxel `.vox` file.
+///
+/// MagicaVoxel is Z up, so its Z becomes our Y. Its Y is flipped into our Z
+/// to keep the model from being mirrored.
+///
+/// Palette indices found in `mapping` use that block. Indices colored exactly like
+/// the block with the same ID, as `write_vox` writes them, use that block,
+/// the rest use the block with the nearest color.
+/// Files without a palette need every index mapped.
+pub fn read_vox(data: &[u8], mapping: &VoxMapping) -> VoxResult<Structure> {
+    let mut reader = Reader { data, position: 0 };
+
+    if reader.take(4)? != b"VOX " {
+        return Err(VoxError::new("Not a .vox file".to_string()));
+    }
+    reader.u32()?;
+
+    let (main, _, children) = reader.chunk_header()?;
+    if &main != b"MAIN" {
+        return Err(VoxError::new("Missing MAIN chunk".to_string()));
+    }
+    let end = reader.position.saturating_add(children).min(data.len());
+
+    let mut size = None;
+    let mut voxels = None;
+    let mut palette = None;
+
+    while reader.position < end {
+        let (id, content, children) = reader.chunk_header()?;
+        let mut content = Reader {
+            data: reader.take(content)?,
+            position: 0,
+        };
+        reader.take(children)?;
+
+        match &id {
+            b"SIZE" if size.is_none() => {
+                size = Some([content.u32()?, content.u32()?, content.u32()?]);
+            }
+            b"XYZI" if voxels.is_none() => {
+                let count = content.u32()? as usize;
+                voxels = Some(content.take(count.saturating_mul(4))?);
+            }
+            b"SIZE" | b"XYZI" => {
+                log::warn!("Only the first model of a .vox file is imported");
+            }
+            b"RGBA" => {
+                let mut colors = [[0; 3]; 256];
+
+                // Color `i` of the chunk belongs to palette index `i + 1`
+                for color in colors.iter_mut().skip(1) {
+                    let rgba = content.take(4)?;
+                    *color = [rgba[0], rgba[1], rgba[2]];
+                }
+                palette = Some(colors);
+            }
+            _ => {}
+        }
+    }
+
+    let (Some([sx, sy, sz]), Some(voxels)) = (size, voxels) else {
+        return Err(VoxError::new("File has no model".to_string()));
+    };
+
+    if sx > 256 || sy > 256 || sz > 256 {
+        return Err(VoxError::new(format!("Model {sx}x{sy}x{sz} is too large")));
+    }
+
+    let mut blocks = HashMap::new();
+    let mut structure = Structure::new([sx as usize, sz as usize, sy as usize]);
+
+    for voxel in voxels.chunks_exact(4) {
+        let (x, y, z, index) = (voxel[0], voxel[1], voxel[2], voxel[3]);
+
+        let block = match blocks.get(&index) {
+            Some(block) => *block,
+            None => {
+                let block = match (mapping.get(index), &palette) {
+                    (Some(block), _) => block,
+                    (None, Some(palette)) => {
+                        let color = palette[index as usize];
+
+                        match Blocks::BLOCKS.get(index as usize) {
+                            Some(block) if block.color == color => block.default_state(),
+                            _ => Blocks::nearest_color(color).default_state(),
+                        }
+                    }
+                    (None, None) => {
+                        return Err(VoxError::new(format!(
+                            "Palette index {index} isn't mapped to a block"
+                        )))
+                    }
+                };
+                blocks.insert(index, block);
+                block
+            }
+        };
+
+        if y as u32 >= sy {
+            continue;
+        }
+
+        structure.set(x as usize, z as usize, (sy - 1 - y as u32) as usize, block);
+    }
+
+    Ok(structure)
+}
+
+pub fn load_vox(path: impl AsRef<Path>, mapping: &VoxMapping) -> VoxResult<Structure> {
+    let data =
+        std::fs::read(path).map_err(|e| VoxError::new(format!("Failed to read file: {e}")))?;
+
+    read_vox(&data, mapping)
+}
+
+/// Writes a structure as a single model `.vox` file.
+///
+/// Block IDs are used as palette indices, colored with each block's color,
+/// so that the file imports back to the same blocks.
+pub fn write_vox(structure: &Structure) -> VoxResult<Vec<u8>> {
+    let [sx, sy, sz] = structure.size();
+
+    if sx > 256 || sy > 256 || sz > 256 {
+        return Err(VoxError::new(format!(
+            "Region {sx}x{sy}x{sz} is larger than 256 in some direction"
+        )));
+    }
+
+    let mut size = Vec::new();
+    for side in [sx, sz, sy] {
+        size.extend_from_slice(&(side as u32).to_le_bytes());
+    }
+
+    let mut xyzi = Vec::new();
+    let mut count = 0u32;
+    for ([x, y, z], voxel) in structure.iter() {
+        if voxel == Blocks::AIR.default_state() {
+            continue;
+        }
+
+        xyzi.extend_from_slice(&[x as u8, (sz - 1 - z) as u8, y as u8, voxel.id]);
+        count += 1;
+    }
+    xyzi.splice(0..0, count.to_le_bytes());
+
+    let mut rgba = Vec::with_capacity(256 * 4);
+    for index in 1..=256 {
+        let color = Blocks::BLOCKS
+            .get(index)
+            .map(|block| block.color)
+            .unwrap_or_default();
+
+        rgba.extend_from_slice(&color);
+        rgba.push(255);
+    }
+
+    let mut children = Vec::new();
+    write_chunk(&mut children, b"SIZE", &size);
+    write_chunk(&mut children, b"XYZI", &xyzi);
+    write_chunk(&mut children, b"RGBA", &rgba);
+
+    let mut data = Vec::new();
+    data.extend_from_slice(b"VOX ");
+    data.extend_from_slice(&150u32.to_le_bytes());
+    data.extend_from_slice(b"MAIN");
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&(children.len() as u32).to_le_bytes());
+    data.extend_from_slice(&children);
+
+    Ok(data)
+}
+
+pub fn save_vox(path: impl AsRef<Path>, structure: &Structure) -> VoxResult<()> {
+    std::fs::write(path, write_vox(structure)?)
+        .map_err(|e| VoxError::new(format!("Failed to write file: {e}")))
+}
+
+fn write_chunk(data: &mut Vec<u8>, id: &[u8; 4], content: &[u8]) {
+    data.extend_from_slice(id);
+    data.extend_from_slice(&(content.len() as u32).to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(content);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> VoxResult<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| VoxError::new("Unexpected end of file".to_string()))?;
+
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> VoxResult<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Chunk ID, content size and children size
+    fn chunk_header(&mut self) -> VoxResult<([u8; 4], usize, usize)> {
+        let id = self.take(4)?.try_into().unwrap();
+
+        Ok((id, self.u32()? as usize, self.u32()? as usize))
+    }
+}
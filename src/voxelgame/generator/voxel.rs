@@ -1,25 +1,99 @@
 type VoxelId = u8;
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct Voxel {
     pub id: VoxelId,
 }
 
+/// Axis aligned box within a block, from `0.0` to `1.0` on every axis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockBox {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl BlockBox {
+    pub const FULL: Self = Self::new([0.0; 3], [1.0; 3]);
+
+    pub const fn new(min: [f32; 3], max: [f32; 3]) -> Self {
+        Self { min, max }
+    }
+
+    /// Box given in sixteenths of a block, like texture pixels
+    pub const fn pixels(min: [u8; 3], max: [u8; 3]) -> Self {
+        Self::new(
+            [
+                min[0] as f32 / 16.0,
+                min[1] as f32 / 16.0,
+                min[2] as f32 / 16.0,
+            ],
+            [
+                max[0] as f32 / 16.0,
+                max[1] as f32 / 16.0,
+                max[2] as f32 / 16.0,
+            ],
+        )
+    }
+}
+
+/// Shape a block is meshed as
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlockModel {
+    /// Not meshed at all
+    None,
+    Cube,
+    /// Bottom half of a block
+    Slab,
+    /// Slab with a step on top at the back (+Z) half
+    Stairs,
+    /// Two crossed quads, for plants
+    Cross,
+    /// Thin post in the middle of the block
+    FencePost,
+    Boxes(&'static [BlockBox]),
+}
+
+impl BlockModel {
+    const SLAB: &'static [BlockBox] = &[BlockBox::new([0.0; 3], [1.0, 0.5, 1.0])];
+    const STAIRS: &'static [BlockBox] = &[
+        BlockBox::new([0.0; 3], [1.0, 0.5, 1.0]),
+        BlockBox::new([0.0, 0.5, 0.5], [1.0; 3]),
+    ];
+    const FENCE_POST: &'static [BlockBox] = &[BlockBox::pixels([6, 0, 6], [10, 16, 10])];
+
+    /// Boxes the model is made of, none for models that aren't boxes
+    pub fn boxes(&self) -> &'static [BlockBox] {
+        match self {
+            Self::None | Self::Cross => &[],
+            Self::Cube => &[BlockBox::FULL],
+            Self::Slab => Self::SLAB,
+            Self::Stairs => Self::STAIRS,
+            Self::FencePost => Self::FENCE_POST,
+            Self::Boxes(boxes) => boxes,
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Clone, Copy, Debug)]
 pub struct RegisteredBlock {
     pub name: &'static str,
     pub transparent: bool,
     pub solid: bool,
+    /// Bodies inside fluids swim instead of walking
+    pub fluid: bool,
+    /// Average color, used to match colored voxel models to blocks
+    pub color: [u8; 3],
+    pub model: BlockModel,
 
-    // IDs in order:
+    // Texture names in order, loaded from `assets/textures/blocks/<name>.png`:
     // 0: left
     // 1: right
     // 2: top
     // 3: bottom
     // 4: back
     // 5: front
-    pub texture_ids: [usize; 6],
+    pub textures: [&'static str; 6],
     default_state: Voxel,
 }
 
@@ -39,7 +113,11 @@ impl Blocks {
         name: "Air",
         transparent: true,
         solid: false,
-        texture_ids: [0; 6],
+        fluid: false,
+        color: [0, 0, 0],
+        model: BlockModel::None,
+        // Never meshed, so it has no textures
+        textures: [""; 6],
         default_state: Voxel { id: 0 },
     };
 
@@ -47,7 +125,10 @@ impl Blocks {
         name: "Stone",
         transparent: false,
         solid: true,
-        texture_ids: [1; 6],
+        fluid: false,
+        color: [125, 125, 125],
+        model: BlockModel::Cube,
+        textures: ["stone"; 6],
         default_state: Voxel { id: 1 },
     };
 
@@ -55,7 +136,17 @@ impl Blocks {
         name: "Grass Block",
         transparent: false,
         solid: true,
-        texture_ids: [3, 3, 2, 4, 3, 3],
+        fluid: false,
+        color: [95, 159, 53],
+        model: BlockModel::Cube,
+        textures: [
+            "grass_side",
+            "grass_side",
+            "grass_top",
+            "dirt",
+            "grass_side",
+            "grass_side",
+        ],
         default_state: Voxel { id: 2 },
     };
 
@@ -63,7 +154,10 @@ impl Blocks {
         name: "Dirt",
         transparent: false,
         solid: true,
-        texture_ids: [4; 6],
+        fluid: false,
+        color: [134, 96, 67],
+        model: BlockModel::Cube,
+        textures: ["dirt"; 6],
         default_state: Voxel { id: 3 },
     };
 
@@ -71,7 +165,10 @@ impl Blocks {
         name: "Log",
         transparent: false,
         solid: true,
-        texture_ids: [5, 5, 6, 6, 5, 5],
+        fluid: false,
+        color: [102, 81, 51],
+        model: BlockModel::Cube,
+        textures: ["log", "log", "log_top", "log_top", "log", "log"],
         default_state: Voxel { id: 4 },
     };
 
@@ -79,10 +176,118 @@ impl Blocks {
         name: "Sand",
         transparent: false,
         solid: true,
-        texture_ids: [7; 6],
+        fluid: false,
+        color: [219, 207, 163],
+        model: BlockModel::Cube,
+        textures: ["sand"; 6],
         default_state: Voxel { id: 5 },
     };
 
+    pub const LEAVES: RegisteredBlock = RegisteredBlock {
+        name: "Leaves",
+        transparent: true,
+        solid: true,
+        fluid: false,
+        color: [34, 130, 30],
+        model: BlockModel::Cube,
+        textures: ["leaves"; 6],
+        default_state: Voxel { id: 6 },
+    };
+
+    pub const TALL_GRASS: RegisteredBlock = RegisteredBlock {
+        name: "Tall Grass",
+        transparent: true,
+        solid: false,
+        fluid: false,
+        color: [40, 160, 35],
+        model: BlockModel::Cross,
+        textures: ["tall_grass"; 6],
+        default_state: Voxel { id: 7 },
+    };
+
+    pub const POPPY: RegisteredBlock = RegisteredBlock {
+        name: "Poppy",
+        transparent: true,
+        solid: false,
+        fluid: false,
+        color: [200, 30, 25],
+        model: BlockModel::Cross,
+        textures: ["poppy"; 6],
+        default_state: Voxel { id: 8 },
+    };
+
+    pub const DANDELION: RegisteredBlock = RegisteredBlock {
+        name: "Dandelion",
+        transparent: true,
+        solid: false,
+        fluid: false,
+        color: [240, 210, 40],
+        model: BlockModel::Cross,
+        textures: ["dandelion"; 6],
+        default_state: Voxel { id: 9 },
+    };
+
+    pub const DEAD_BUSH: RegisteredBlock = RegisteredBlock {
+        name: "Dead Bush",
+        transparent: true,
+        solid: false,
+        fluid: false,
+        color: [120, 85, 45],
+        model: BlockModel::Cross,
+        textures: ["dead_bush"; 6],
+        default_state: Voxel { id: 10 },
+    };
+
+    pub const STONE_SLAB: RegisteredBlock = RegisteredBlock {
+        name: "Stone Slab",
+        transparent: false,
+        solid: true,
+        fluid: false,
+        color: [125, 125, 125],
+        model: BlockModel::Slab,
+        textures: ["stone"; 6],
+        default_state: Voxel { id: 11 },
+    };
+
+    pub const STONE_STAIRS: RegisteredBlock = RegisteredBlock {
+        name: "Stone Stairs",
+        transparent: false,
+        solid: true,
+        fluid: false,
+        color: [125, 125, 125],
+        model: BlockModel::Stairs,
+        textures: ["stone"; 6],
+        default_state: Voxel { id: 12 },
+    };
+
+    pub const FENCE: RegisteredBlock = RegisteredBlock {
+        name: "Fence",
+        transparent: false,
+        solid: true,
+        fluid: false,
+        color: [102, 81, 51],
+        model: BlockModel::FencePost,
+        textures: ["log", "log", "log_top", "log_top", "log", "log"],
+        default_state: Voxel { id: 13 },
+    };
+
+    pub const TABLE: RegisteredBlock = RegisteredBlock {
+        name: "Table",
+        transparent: false,
+        solid: true,
+        fluid: false,
+        color: [102, 81, 51],
+        model: BlockModel::Boxes(&[
+            BlockBox::pixels([0, 13, 0], [16, 16, 16]),
+            BlockBox::pixels([1, 0, 1], [3, 13, 3]),
+            BlockBox::pixels([13, 0, 1], [15, 13, 3]),
+            BlockBox::pixels([1, 0, 13], [3, 13, 15]),
+            BlockBox::pixels([13, 0, 13], [15, 13, 15]),
+        ]),
+        textures: ["log", "log", "log_top", "log_top", "log", "log"],
+        default_state: Voxel { id: 14 },
+    };
+
     pub const BLOCKS: &'static [RegisteredBlock] = &[
         Self::AIR,
         Self::STONE,
@@ -90,5 +295,39 @@ impl Blocks {
         Self::DIRT_BLOCK,
         Self::LOG,
         Self::SAND,
+        Self::LEAVES,
+        Self::TALL_GRASS,
+        Self::POPPY,
+        Self::DANDELION,
+        Self::DEAD_BUSH,
+        Self::STONE_SLAB,
+        Self::STONE_STAIRS,
+        Self::FENCE,
+        Self::TABLE,
     ];
+
+    /// Full opaque cube whose color is closest to `color`
+    pub fn nearest_color(color: [u8; 3]) -> &'static RegisteredBlock {
+        let distance = |other: [u8; 3]| {
+            (0..3)
+                .map(|i| (color[i] as i32 - other[i] as i32).pow(2))
+                .sum::<i32>()
+        };
+
+        Self::BLOCKS
+            .iter()
+            .filter(|block| block.model == BlockModel::Cube && !block.transparent)
+            .min_by_key(|block| distance(block.color))
+            .unwrap()
+    }
+
+    /// Looks a block up by name, ignoring case and treating spaces and underscores alike
+    pub fn by_name(name: &str) -> Option<&'static RegisteredBlock> {
+        let normalize = |name: &str| name.trim().to_lowercase().replace(' ', "_");
+        let name = normalize(name);
+
+        Self::BLOCKS
+            .iter()
+            .find(|block| normalize(block.name) == name)
+    }
 }
@@ -3,8 +3,6 @@ use std::ops::{AddAssign, Range};
 use bytemuck::{Pod, Zeroable};
 use wgpu::util::DeviceExt;
 
-use super::draw::Drawable;
-
 pub trait Vertex: Pod + Zeroable {
     fn attribs() -> &'static [wgpu::VertexAttribute];
     fn desc() -> wgpu::VertexBufferLayout<'static> {
@@ -28,18 +26,22 @@ pub trait Instance: Pod + Zeroable {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
 pub struct Vertex3d {
     pub position: [f32; 3],
     pub normal: [f32; 3],
+    /// UV within the texture at `layer`
     pub uv: [f32; 2],
+    /// Layer of the terrain texture array
+    pub layer: u32,
 }
 
 impl Vertex3d {
     const ATTRIBS: &'static [wgpu::VertexAttribute] = &wgpu::vertex_attr_array![
         0 => Float32x3,
         1 => Float32x3,
-        2 => Float32x2
+        2 => Float32x2,
+        3 => Uint32
     ];
 }
 
@@ -49,9 +51,114 @@ impl Vertex for Vertex3d {
     }
 }
 
+/// Chunk mesh vertex packed into 8 bytes, decoded in `opaque.wgsl`.
+///
+/// Bits of the first word, from the lowest:
+/// - 0..30: position within the chunk in sixteenths of a block, 10 bits per axis
+///
+/// Bits of the second word:
+/// - 0..3: face, indexing `ChunkVertex::NORMALS`
+/// - 3..8 and 8..13: UV within the texture in sixteenths
+/// - 13..24: texture array layer
+/// - 24..32: light, 255 being fully lit
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Pod, Zeroable)]
+pub struct ChunkVertex {
+    pub position: u32,
+    pub data: u32,
+}
+
+#[allow(dead_code)]
+impl ChunkVertex {
+    const ATTRIBS: &'static [wgpu::VertexAttribute] = &wgpu::vertex_attr_array![
+        0 => Uint32,
+        1 => Uint32
+    ];
+
+    /// Normals of every face index, the order faces are meshed in
+    pub const NORMALS: [[f32; 3]; 6] = [
+        [-1.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, -1.0, 0.0],
+        [0.0, 0.0, 1.0],
+        [0.0, 0.0, -1.0],
+    ];
+
+    /// Positions and UVs are snapped to sixteenths of a block
+    pub const SUBDIVISIONS: f32 = 16.0;
+    pub const MAX_LAYERS: u32 = 1 << 11;
+
+    pub fn new(position: [u32; 3], face: u32, uv: [u32; 2], layer: u32, light: u8) -> Self {
+        debug_assert!(position.iter().all(|axis| *axis < 1 << 10));
+        debug_assert!(face < 6 && uv.iter().all(|axis| *axis < 1 << 5));
+        debug_assert!(layer < Self::MAX_LAYERS);
+
+        Self {
+            position: position[0] | position[1] << 10 | position[2] << 20,
+            data: face | uv[0] << 3 | uv[1] << 8 | layer << 13 | (light as u32) << 24,
+        }
+    }
+
+    /// Packs a fully lit vertex, its normal has to be one of `NORMALS`
+    pub fn pack(vertex: &Vertex3d) -> Self {
+        let snap = |value: f32| (value * Self::SUBDIVISIONS).round() as u32;
+        let face = Self::NORMALS
+            .iter()
+            .position(|normal| *normal == vertex.normal)
+            .expect("Chunk vertex normals are axis aligned") as u32;
+
+        Self::new(
+            vertex.position.map(snap),
+            face,
+            vertex.uv.map(snap),
+            vertex.layer,
+            u8::MAX,
+        )
+    }
+
+    pub fn pack_mesh(mesh: MeshInfo<Vertex3d>) -> MeshInfo<Self> {
+        MeshInfo {
+            vertices: mesh.vertices.iter().map(Self::pack).collect(),
+            indices: mesh.indices,
+            face_ranges: mesh.face_ranges,
+        }
+    }
+
+    /// Decodes the vertex the same way the shader does
+    pub fn unpack(&self) -> Vertex3d {
+        let bits = |word: u32, offset: u32, count: u32| (word >> offset) & ((1 << count) - 1);
+        let position =
+            [0, 10, 20].map(|offset| bits(self.position, offset, 10) as f32 / Self::SUBDIVISIONS);
+
+        Vertex3d {
+            position,
+            normal: Self::NORMALS[bits(self.data, 0, 3) as usize],
+            uv: [
+                bits(self.data, 3, 5) as f32 / Self::SUBDIVISIONS,
+                bits(self.data, 8, 5) as f32 / Self::SUBDIVISIONS,
+            ],
+            layer: bits(self.data, 13, 11),
+        }
+    }
+
+    pub fn light(&self) -> u8 {
+        (self.data >> 24) as u8
+    }
+}
+
+impl Vertex for ChunkVertex {
+    fn attribs() -> &'static [wgpu::VertexAttribute] {
+        Self::ATTRIBS
+    }
+}
+
 pub struct MeshInfo<T> {
     pub vertices: Vec<T>,
     pub indices: Vec<u32>,
+    /// Ranges of `indices` with the faces pointing in each direction, in `ChunkVertex::NORMALS` order.
+    /// Indices after the last range are of faces seen from every direction.
+    pub face_ranges: [Range<u32>; 6],
 }
 
 impl<T> MeshInfo<T> {
@@ -59,20 +166,63 @@ impl<T> MeshInfo<T> {
         Self {
             vertices: Vec::new(),
             indices: Vec::new(),
+            face_ranges: Default::default(),
+        }
+    }
+
+    /// Lays out the indices of faces grouped by direction one group after another
+    pub fn from_faces(vertices: Vec<T>, faces: [Vec<u32>; 6], any_direction: Vec<u32>) -> Self {
+        let mut indices =
+            Vec::with_capacity(faces.iter().map(Vec::len).sum::<usize>() + any_direction.len());
+        let face_ranges = faces.map(|face| {
+            let start = indices.len() as u32;
+            indices.extend(face);
+            start..indices.len() as u32
+        });
+        indices.extend(any_direction);
+
+        Self {
+            vertices,
+            indices,
+            face_ranges,
         }
     }
 
+    /// Indices of faces pointing in `direction`, or with `None` of faces seen from every direction
+    pub fn face_indices(&self, direction: Option<usize>) -> &[u32] {
+        let range = match direction {
+            Some(direction) => self.face_ranges[direction].clone(),
+            None => self.face_ranges[5].end..self.indices.len() as u32,
+        };
+
+        &self.indices[range.start as usize..range.end as usize]
+    }
+
     #[deprecated = "Don't use"]
     pub fn transform_vertices(&mut self, f: fn(v: &mut T)) {
         self.vertices.iter_mut().for_each(f);
     }
 
     pub fn merge(&mut self, mut rhs: Self) {
-        rhs.indices
-            .iter_mut()
-            .for_each(|i| *i += self.vertices.len() as u32);
-        self.indices.append(&mut rhs.indices);
-        self.vertices.append(&mut rhs.vertices);
+        let offset = self.vertices.len() as u32;
+        let merged = |direction: Option<usize>| {
+            let mut indices = self.face_indices(direction).to_vec();
+            indices.extend(rhs.face_indices(direction).iter().map(|i| i + offset));
+            indices
+        };
+
+        let faces = std::array::from_fn(|direction| merged(Some(direction)));
+        let any_direction = merged(None);
+
+        let mut vertices = std::mem::take(&mut self.vertices);
+        vertices.append(&mut rhs.vertices);
+        *self = Self::from_faces(vertices, faces, any_direction);
+    }
+}
+
+impl<T> Default for MeshInfo<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -115,10 +265,6 @@ impl Mesh {
         }
     }
 
-    pub fn from_info<T: Vertex>(device: &wgpu::Device, info: MeshInfo<T>) -> Self {
-        Self::create(device, &info.vertices, &info.indices)
-    }
-
     pub fn draw_instanced(
         &self,
         render_pass: &mut wgpu::RenderPass,
@@ -132,13 +278,3 @@ impl Mesh {
         render_pass.draw_indexed(0..self.element_count as u32, 0, instances);
     }
 }
-
-impl Drawable for Mesh {
-    fn draw(&self, render_pass: &mut wgpu::RenderPass) {
-        // TODO: Move to bundle?
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-
-        render_pass.draw_indexed(0..self.element_count as u32, 0, 0..1);
-    }
-}
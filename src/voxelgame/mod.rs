@@ -1,22 +1,42 @@
+mod atlas;
+mod brush;
 mod camera;
+mod chunk_buffers;
 mod debug;
-mod draw;
+mod export;
 mod font;
 mod generator;
 mod mesh;
+mod player;
+mod selection;
 mod tests;
 mod texture;
 
 use std::{collections::HashMap, sync::Arc, time::Instant};
 
+use brush::Brush;
 use camera::{Camera, CameraController};
-use cgmath::{EuclideanSpace, InnerSpace};
+use cgmath::EuclideanSpace;
+use chunk_buffers::ChunkDrawMode;
 use debug::{DebugDrawer, DebugModelInstance, DebugVertex};
-use generator::{chunk::BlockOffsetCoord, voxel::Blocks, NoiseGenerator, Ray, World};
-use mesh::{Instance, Vertex, Vertex3d};
+use generator::{
+    config::NoiseGeneratorConfig,
+    edit::WorldEdit,
+    feature::StructureFeature,
+    raycast::{RayFilter, RayHit},
+    schematic::Schematic,
+    spec::GeneratorSpec,
+    structure::Structure,
+    vox::VoxMapping,
+    voxel::Blocks,
+    Generator, NoiseGenerator, Ray, World,
+};
+use mesh::{ChunkVertex, Instance, Vertex};
+use player::{MovementMode, Player};
 use pollster::FutureExt;
 use rand::Rng;
-use texture::Texture2d;
+use selection::Selection;
+use texture::{Texture2d, TextureConfig};
 use wgpu::util::DeviceExt;
 use winit::{
     dpi::PhysicalSize,
@@ -43,7 +63,10 @@ pub struct VoxelGame<'w> {
     prev_time: f32,
 
     depth_texture: Texture2d,
-    world: World<NoiseGenerator>,
+    world: World<Box<dyn Generator>>,
+    /// Kept to recreate the generator when reloading it
+    generator_spec: GeneratorSpec,
+    seed: i32,
     debug: DebugDrawer,
 
     pipelines: HashMap<String, wgpu::RenderPipeline>,
@@ -51,8 +74,15 @@ pub struct VoxelGame<'w> {
     bind_layouts: HashMap<String, wgpu::BindGroupLayout>,
     uniform_buffers: HashMap<String, wgpu::Buffer>,
     textures: HashMap<String, Texture2d>,
+    /// Block texture atlas encoded as PNG, written next to exported meshes
+    atlas_png: Vec<u8>,
     camera: Camera,
     camera_controller: CameraController,
+    player: Player,
+    movement_mode: MovementMode,
+    brush: Brush,
+    selection: Selection,
+    clipboard: Option<Structure>,
 
     generate: bool,
     draw_debug: bool,
@@ -60,6 +90,27 @@ pub struct VoxelGame<'w> {
 }
 
 impl<'w> VoxelGame<'w> {
+    /// Maximum distance at which blocks can be targeted
+    const REACH: f32 = 256.0;
+    /// Where the clipboard is saved to and loaded from
+    const CLIPBOARD_FILE: &'static str = "clipboard.vxschem";
+    /// Schematics in this directory are scattered over the terrain
+    const FEATURES_DIR: &'static str = "assets/structures";
+    /// MagicaVoxel model imported at the targeted block
+    const VOX_IMPORT_FILE: &'static str = "import.vox";
+    /// Optional palette index to block table used for `VOX_IMPORT_FILE`
+    const VOX_MAPPING_FILE: &'static str = "import.vox.txt";
+    /// Where the selection is exported to as a MagicaVoxel model
+    const VOX_EXPORT_FILE: &'static str = "export.vox";
+    /// Directory chunk meshes are exported to as OBJ and glTF
+    const MESH_EXPORT_DIR: &'static str = "export";
+    /// Mipmap and sampler settings of the terrain textures
+    const TEXTURE_CONFIG_FILE: &'static str = "assets/textures.toml";
+    /// Noise terrain settings, reloaded with F5
+    const NOISE_CONFIG_FILE: &'static str = "assets/noise.toml";
+    const FEATURE_SPACING: u32 = 128;
+    const FEATURE_CHANCE: f32 = 0.25;
+
     pub async fn new(window: Arc<Window>) -> Self {
         let size = window.inner_size();
 
@@ -86,7 +137,8 @@ impl<'w> VoxelGame<'w> {
             .request_device(&wgpu::DeviceDescriptor {
                 label: Some("graphics_device"),
                 memory_hints: wgpu::MemoryHints::Performance,
-                required_features: wgpu::Features::POLYGON_MODE_LINE,
+                required_features: wgpu::Features::POLYGON_MODE_LINE
+                    | (adapter.features() & ChunkDrawMode::FEATURES),
                 required_limits: wgpu::Limits::default(),
                 trace: wgpu::Trace::Off,
                 experimental_features: wgpu::ExperimentalFeatures::disabled(),
@@ -128,7 +180,25 @@ impl<'w> VoxelGame<'w> {
         let camera_controller = CameraController::new(5.0, 0.003);
 
         let uniform_buffers = Self::create_uniform_buffers(&device, &camera);
-        let textures = Self::create_textures(&device, &queue);
+        let block_textures = atlas::layout()
+            .load_embedded()
+            .unwrap_or_else(|e| panic!("Failed to load block textures: {e}"));
+        let texture_config = TextureConfig::load_or_default(Self::TEXTURE_CONFIG_FILE)
+            .unwrap_or_else(|e| {
+                log::error!("{e}, using default texture settings");
+                TextureConfig::default()
+            });
+
+        let mut atlas_png = Vec::new();
+        atlas::layout()
+            .pack(&block_textures)
+            .write_to(
+                &mut std::io::Cursor::new(&mut atlas_png),
+                image::ImageFormat::Png,
+            )
+            .expect("Failed to encode texture atlas");
+
+        let textures = Self::create_textures(&device, &queue, &block_textures, &texture_config);
         let (bind_groups, bind_layouts) =
             Self::create_bind_groups(&device, &uniform_buffers, &textures);
 
@@ -140,7 +210,17 @@ impl<'w> VoxelGame<'w> {
         let debug = DebugDrawer::new(&device);
 
         let mut rng = rand::rng();
-        let mut world = World::new(NoiseGenerator::new(rng.random_range(i32::MIN..i32::MAX)));
+        let seed = rng.random_range(i32::MIN..i32::MAX);
+        let generator_spec = Self::generator_spec();
+        let generator = Self::create_generator(&generator_spec, seed).unwrap_or_else(|e| {
+            log::error!("Failed to create generator: {e}");
+            Box::new(NoiseGenerator::new(seed))
+        });
+
+        let mut world = World::new(generator);
+        let draw_mode = ChunkDrawMode::supported(&adapter, &device);
+        log::info!("Drawing chunks with {draw_mode:?}");
+        world.set_draw_mode(draw_mode);
 
         world.dispatch_threads(4, 4);
 
@@ -163,6 +243,8 @@ impl<'w> VoxelGame<'w> {
 
             depth_texture, // TODO: Move depth texture to textures hashmap
             world,
+            generator_spec,
+            seed,
             debug,
 
             start_time: Instant::now(),
@@ -173,6 +255,12 @@ impl<'w> VoxelGame<'w> {
             bind_layouts,
             uniform_buffers,
             textures,
+            atlas_png,
+            player: Player::from_eye(camera.eye),
+            movement_mode: MovementMode::Fly,
+            brush: Brush::default(),
+            selection: Selection::default(),
+            clipboard: None,
             camera,
             camera_controller,
 
@@ -198,14 +286,19 @@ impl<'w> VoxelGame<'w> {
         return map;
     }
 
-    fn create_textures(device: &wgpu::Device, queue: &wgpu::Queue) -> HashMap<String, Texture2d> {
-        let terrain_texture = Texture2d::from_image_bytes(
-            include_bytes!("../../assets/textures/textureatlas.png"),
+    fn create_textures(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        block_textures: &[image::RgbaImage],
+        config: &TextureConfig,
+    ) -> HashMap<String, Texture2d> {
+        let terrain_texture = Texture2d::from_layers(
+            block_textures,
             device,
             queue,
+            config,
             Some("terrain_texture"),
-        )
-        .expect("Failed to load image");
+        );
 
         let mut textures = HashMap::new();
 
@@ -228,7 +321,7 @@ impl<'w> VoxelGame<'w> {
                 binding: 0,
                 visibility: wgpu::ShaderStages::VERTEX,
                 ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
                     has_dynamic_offset: false,
                     min_binding_size: None,
                 },
@@ -258,7 +351,7 @@ impl<'w> VoxelGame<'w> {
                     visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Texture {
                         sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
                         multisampled: false,
                     },
                     count: None,
@@ -336,7 +429,7 @@ impl<'w> VoxelGame<'w> {
                 module: &opaque_module,
                 entry_point: Some("vs_main"),
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
-                buffers: &[Vertex3d::desc()],
+                buffers: &[ChunkVertex::desc()],
             },
             fragment: Some(wgpu::FragmentState {
                 module: &opaque_module,
@@ -479,8 +572,164 @@ impl<'w> VoxelGame<'w> {
         );
     }
 
+    fn look_ray(&self) -> Ray {
+        Ray {
+            origin: self.camera.eye,
+            direction: -self.camera.direction, // TODO: Figure out why negative
+        }
+    }
+
+    /// Generator named by the `--generator <spec>` argument, see `GeneratorSpec::parse`.
+    /// Noise terrain if there is none or it is invalid.
+    fn generator_spec() -> GeneratorSpec {
+        let mut args = std::env::args().skip(1);
+        let mut spec = None;
+
+        while let Some(arg) = args.next() {
+            if let Some(value) = arg.strip_prefix("--generator=") {
+                spec = Some(value.to_string());
+            } else if arg == "--generator" {
+                spec = args.next();
+            }
+        }
+
+        match spec.as_deref().map(GeneratorSpec::parse) {
+            None => GeneratorSpec::Noise,
+            Some(Ok(spec)) => spec,
+            Some(Err(e)) => {
+                log::error!("Invalid generator: {e}");
+                GeneratorSpec::Noise
+            }
+        }
+    }
+
+    /// Creates the generator, reading its files again so that edits to them are picked up.
+    /// Noise terrain is configured by `NOISE_CONFIG_FILE`.
+    fn create_generator(spec: &GeneratorSpec, seed: i32) -> Result<Box<dyn Generator>, String> {
+        let GeneratorSpec::Noise = spec else {
+            return spec.build(seed);
+        };
+
+        let config = NoiseGeneratorConfig::load_or_default(Self::NOISE_CONFIG_FILE)
+            .map_err(|e| format!("{}: {e}", Self::NOISE_CONFIG_FILE))?;
+        let mut generator = NoiseGenerator::with_config(seed, config);
+
+        for (salt, schematic) in Self::load_feature_schematics().into_iter().enumerate() {
+            generator = generator.with_feature(StructureFeature::new(
+                schematic.structure,
+                Self::FEATURE_SPACING,
+                Self::FEATURE_CHANCE,
+                salt as u64,
+            ));
+        }
+
+        Ok(Box::new(generator))
+    }
+
+    /// Recreates the generator from its files and regenerates the world,
+    /// keeping the current world if that fails
+    fn reload_generator(&mut self) {
+        match Self::create_generator(&self.generator_spec, self.seed) {
+            Ok(generator) => {
+                self.world.set_generator(generator);
+                log::info!("Reloaded generator");
+            }
+            Err(e) => log::error!("Failed to reload generator: {e}"),
+        }
+    }
+
+    /// Loads every schematic in `FEATURES_DIR` to be placed by worldgen
+    fn load_feature_schematics() -> Vec<Schematic> {
+        let Ok(entries) = std::fs::read_dir(Self::FEATURES_DIR) else {
+            return Vec::new();
+        };
+
+        let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+        paths.sort();
+
+        paths
+            .into_iter()
+            .filter_map(|path| match Schematic::load(&path) {
+                Ok(schematic) => {
+                    log::info!("Loaded worldgen feature {}", path.display());
+                    Some(schematic)
+                }
+                Err(e) => {
+                    log::error!("Failed to load {}: {e}", path.display());
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Places `VOX_IMPORT_FILE` with its minimum corner at `origin`
+    fn import_vox(&mut self, origin: generator::chunk::WorldCoord) {
+        let mapping = if std::path::Path::new(Self::VOX_MAPPING_FILE).exists() {
+            match VoxMapping::load(Self::VOX_MAPPING_FILE) {
+                Ok(mapping) => mapping,
+                Err(e) => {
+                    log::error!("Failed to load {}: {e}", Self::VOX_MAPPING_FILE);
+                    return;
+                }
+            }
+        } else {
+            VoxMapping::new()
+        };
+
+        match generator::vox::load_vox(Self::VOX_IMPORT_FILE, &mapping) {
+            Ok(structure) => self
+                .world
+                .apply_user_edit(&structure.paste_edit(origin, false)),
+            Err(e) => log::error!("Failed to import {}: {e}", Self::VOX_IMPORT_FILE),
+        }
+    }
+
+    /// Writes the chunks between `min` and `max` to `MESH_EXPORT_DIR`
+    fn export_meshes(&self, min: generator::chunk::ChunkCoord, max: generator::chunk::ChunkCoord) {
+        let meshes = self.world.chunk_meshes(min, max);
+        let directory = std::path::Path::new(Self::MESH_EXPORT_DIR);
+
+        let result = std::fs::create_dir_all(directory)
+            .map_err(|e| export::ExportError::new(format!("Failed to create directory: {e}")))
+            .and_then(|()| export::obj::save_obj(directory, "world", &meshes, &self.atlas_png))
+            .and_then(|()| {
+                export::gltf::save_glb(directory.join("world.glb"), &meshes, &self.atlas_png)
+            });
+
+        match result {
+            Ok(()) => log::info!(
+                "Exported {} chunks to {}",
+                meshes.len(),
+                directory.display()
+            ),
+            Err(e) => log::error!("Failed to export chunks: {e}"),
+        }
+    }
+
+    /// Solid voxel the player is looking at
+    fn look_hit(&self) -> Option<RayHit> {
+        self.world
+            .ray_hit(self.look_ray(), Self::REACH, RayFilter::Solid, None)
+    }
+
     fn update(&mut self, delta: f32) {
-        self.camera_controller.update(&mut self.camera, delta);
+        match self.movement_mode {
+            MovementMode::Fly => self.camera_controller.update(&mut self.camera, delta),
+            MovementMode::Walk => {
+                self.camera_controller.rotate(&mut self.camera);
+
+                self.player.update(
+                    &self.world,
+                    self.camera_controller.walk_direction(&self.camera),
+                    self.camera_controller.jump_held(),
+                    delta,
+                );
+                self.camera.eye = self.player.eye();
+            }
+        }
+
+        self.debug
+            .set_text("camera.mode", format!("Mode: {:?}", self.movement_mode));
 
         self.debug.set_text(
             "camera.position",
@@ -504,52 +753,77 @@ impl<'w> VoxelGame<'w> {
 
         self.debug.new_frame();
         self.world.append_debug(&mut self.debug);
+        if self.draw_debug {
+            self.world.append_debug_outlines(&mut self.debug);
+        }
 
         let hit = self.world.ray_hit(
-            Ray {
-                origin: self.camera.eye,
-                direction: -self.camera.direction, // TODO: Figure out why negative
-            },
-            Some(&mut self.debug),
+            self.look_ray(),
+            Self::REACH,
+            RayFilter::Solid,
+            self.draw_debug.then_some(&mut self.debug),
         );
 
-        if let Some((position, hit_point, _)) = hit {
-            self.debug.append_mesh(
-                debug::ModelName::Cube,
-                cgmath::Vector3::new(position.x as f32, position.y as f32, position.z as f32)
-                    - cgmath::Vector3::new(0.005, 0.005, 0.005),
-                cgmath::Vector3::new(1.01, 1.01, 1.01),
-                cgmath::Vector4::new(0.0, 0.0, 0.0, 1.0),
-            );
-
-            let position_world: cgmath::Vector3<f32> = position.into();
-            let hit_offset = hit_point - position_world;
-
-            let normal = if hit_offset.x.abs() > hit_offset.y.abs()
-                && hit_offset.x.abs() > hit_offset.z.abs()
-            {
-                cgmath::Vector3::<f32>::new(hit_offset.x.ceil(), 0.0, 0.0)
-            } else if hit_offset.y.abs() > hit_offset.x.abs()
-                && hit_offset.y.abs() > hit_offset.z.abs()
-            {
-                cgmath::Vector3::<f32>::new(0.0, hit_offset.y.ceil(), 0.0)
-            } else if hit_offset.z.abs() > hit_offset.x.abs()
-                && hit_offset.z.abs() > hit_offset.y.abs()
-            {
-                cgmath::Vector3::<f32>::new(0.0, 0.0, hit_offset.z.ceil())
-            } else {
-                cgmath::Vector3::<f32>::new(1.0, 1.0, 1.0)
+        if let Some(hit) = hit {
+            if self.draw_debug {
+                let position: cgmath::Vector3<f32> = hit.coord.into();
+
+                self.debug.append_mesh(
+                    debug::ModelName::Cube,
+                    position - cgmath::Vector3::new(0.005, 0.005, 0.005),
+                    cgmath::Vector3::new(1.01, 1.01, 1.01),
+                    cgmath::Vector4::new(0.0, 0.0, 0.0, 1.0),
+                );
+
+                let normal: cgmath::Vector3<f32> = hit.normal.into();
+
+                self.debug.append_mesh(
+                    debug::ModelName::Cube,
+                    hit.point.to_vec(),
+                    normal + cgmath::Vector3::new(0.01, 0.01, 0.01),
+                    cgmath::Vector4::new(1.0, 1.0, 1.0, 1.0),
+                );
             }
-            .normalize();
 
-            self.debug.append_mesh(
-                debug::ModelName::Cube,
-                hit_point.to_vec(),
-                normal + cgmath::Vector3::new(0.01, 0.01, 0.01),
-                cgmath::Vector4::new(1.0, 1.0, 1.0, 1.0),
-            );
+            let center = self.brush.center(&hit);
+            self.brush
+                .append_preview(&mut self.debug, &self.world, center);
+
+            if let Some(clipboard) = &self.clipboard {
+                let [x, y, z] = clipboard.size();
+
+                self.debug.append_mesh(
+                    debug::ModelName::Cube,
+                    (hit.coord + hit.normal).into(),
+                    cgmath::Vector3::new(x as f32, y as f32, z as f32),
+                    cgmath::Vector4::new(0.0, 1.0, 1.0, 1.0),
+                );
+            }
         }
 
+        self.selection.append_debug(&mut self.debug);
+
+        self.debug.set_text(
+            "tools.brush",
+            format!(
+                "Brush: {:?} {:?} radius {} block {} target {}",
+                self.brush.shape,
+                self.brush.mode,
+                self.brush.radius,
+                Blocks::BLOCKS[self.brush.block.id as usize].name,
+                Blocks::BLOCKS[self.brush.target.id as usize].name,
+            ),
+        );
+
+        self.debug.set_text(
+            "tools.selection",
+            format!(
+                "Selection: {:?} Clipboard: {:?}",
+                self.selection.bounds(),
+                self.clipboard.as_ref().map(|c| c.size()),
+            ),
+        );
+
         self.debug.update_buffer(&mut self.text_queue, &self.queue);
     }
 
@@ -589,7 +863,7 @@ impl<'w> VoxelGame<'w> {
             sky_pass.draw(0..4, 0..1);
         }
 
-        let chunks_drawn;
+        let (chunks_drawn, chunks_culled, chunks_occluded);
         {
             // 1
             let mut opaque_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -620,15 +894,24 @@ impl<'w> VoxelGame<'w> {
             opaque_pass.set_bind_group(1, &self.bind_groups["terrain_texture"], &[]);
             opaque_pass.set_bind_group(2, &self.bind_groups["camera"], &[]);
 
-            chunks_drawn = self
-                .world
-                .draw_distance(&mut opaque_pass, self.camera.eye.to_vec(), 8);
+            (chunks_drawn, chunks_culled, chunks_occluded) = self.world.draw_distance(
+                &self.device,
+                &self.queue,
+                &mut opaque_pass,
+                &self.camera,
+                8,
+            );
         }
 
-        self.debug
-            .set_text("chunks.drawn", format!("Chunks drawn: {}", chunks_drawn,));
+        let draw_mode = self.world.draw_mode();
+        self.debug.set_text(
+            "chunks.drawn",
+            format!(
+                "Chunks drawn: {chunks_drawn} ({draw_mode:?}), culled: {chunks_culled}, occluded: {chunks_occluded}"
+            ),
+        );
 
-        if self.draw_debug {
+        {
             // 2
             let mut debug_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
@@ -750,42 +1033,46 @@ impl<'w> Game for VoxelGame<'w> {
                     match button {
                         winit::event::MouseButton::Left => {
                             let hit = self.world.ray_hit(
-                                Ray {
-                                    origin: self.camera.eye,
-                                    direction: -self.camera.direction,
-                                },
+                                self.look_ray(),
+                                Self::REACH,
+                                RayFilter::Solid,
                                 None,
                             );
 
-                            if let Some((world_coord, _, _)) = hit {
-                                self.world.set_voxels_radius(
-                                    world_coord,
-                                    20,
-                                    Blocks::AIR.default_state(),
-                                );
+                            if let Some(hit) = hit {
+                                let edit = self.brush.edit(&self.world, self.brush.center(&hit));
+                                self.world.apply_user_edit(&edit);
                             }
                         }
-                        winit::event::MouseButton::Right => {
+                        winit::event::MouseButton::Middle => {
                             let hit = self.world.ray_hit(
-                                Ray {
-                                    origin: self.camera.eye,
-                                    direction: -self.camera.direction,
-                                },
+                                self.look_ray(),
+                                Self::REACH,
+                                RayFilter::Solid,
                                 None,
                             );
 
-                            if let Some((world_coord, _, _)) = hit {
-                                // TODO: Fix block placement
-                                let _world_float_coord: cgmath::Vector3<f32> = world_coord.into();
+                            if let Some(hit) = hit {
+                                self.brush.target = hit.voxel;
+                            }
+                        }
+                        winit::event::MouseButton::Right => {
+                            let hit = self.world.ray_hit(
+                                self.look_ray(),
+                                Self::REACH,
+                                RayFilter::Solid,
+                                None,
+                            );
 
-                                let normal = cgmath::Vector3::<f32>::new(0.0, 1.0, 0.0);
-                                let offset: BlockOffsetCoord = normal.into();
+                            if let Some(hit) = hit {
+                                log::info!("placed block with offset {:?}", hit.normal);
 
-                                log::info!("placed block with offset {:?}", offset);
-                                self.world.set_voxel(
-                                    world_coord + offset,
+                                let mut edit = WorldEdit::new();
+                                edit.set(
+                                    hit.coord + hit.normal,
                                     Blocks::DIRT_BLOCK.default_state(),
                                 );
+                                self.world.apply_user_edit(&edit);
                             }
                         }
                         _ => {}
@@ -805,12 +1092,142 @@ impl<'w> Game for VoxelGame<'w> {
                         PhysicalKey::Code(KeyCode::KeyR) => {
                             self.world.reset();
                         }
+                        PhysicalKey::Code(KeyCode::F5) => {
+                            self.reload_generator();
+                        }
                         PhysicalKey::Code(KeyCode::KeyL) => {
                             self.draw_debug = !self.draw_debug;
                         }
                         PhysicalKey::Code(KeyCode::KeyG) => {
                             self.generate = !self.generate;
                         }
+                        PhysicalKey::Code(KeyCode::KeyI) => {
+                            let enabled = !self.world.occlusion_culling();
+                            self.world.set_occlusion_culling(enabled);
+                            log::info!("Occlusion culling: {enabled}");
+                        }
+                        PhysicalKey::Code(KeyCode::KeyZ) => {
+                            self.world.undo();
+                        }
+                        PhysicalKey::Code(KeyCode::KeyY) => {
+                            self.world.redo();
+                        }
+                        PhysicalKey::Code(KeyCode::KeyB) => {
+                            self.brush.shape = self.brush.shape.next();
+                        }
+                        PhysicalKey::Code(KeyCode::KeyM) => {
+                            self.brush.mode = self.brush.mode.next();
+                        }
+                        PhysicalKey::Code(KeyCode::KeyN) => {
+                            self.brush.next_block();
+                        }
+                        PhysicalKey::Code(KeyCode::BracketRight) => {
+                            self.brush.grow();
+                        }
+                        PhysicalKey::Code(KeyCode::BracketLeft) => {
+                            self.brush.shrink();
+                        }
+                        PhysicalKey::Code(KeyCode::Digit1) => {
+                            if let Some(hit) = self.look_hit() {
+                                self.selection.first = Some(hit.coord);
+                            }
+                        }
+                        PhysicalKey::Code(KeyCode::Digit2) => {
+                            if let Some(hit) = self.look_hit() {
+                                self.selection.second = Some(hit.coord);
+                            }
+                        }
+                        PhysicalKey::Code(KeyCode::Digit3) => {
+                            if let Some(edit) = self.selection.fill_edit(self.brush.block) {
+                                self.world.apply_user_edit(&edit);
+                            }
+                        }
+                        PhysicalKey::Code(KeyCode::Digit4) => {
+                            let edit = self.selection.replace_edit(
+                                &self.world,
+                                self.brush.target,
+                                self.brush.block,
+                            );
+
+                            if let Some(edit) = edit {
+                                self.world.apply_user_edit(&edit);
+                            }
+                        }
+                        PhysicalKey::Code(KeyCode::Digit5) => {
+                            if let Some(edit) = self.selection.hollow_edit() {
+                                self.world.apply_user_edit(&edit);
+                            }
+                        }
+                        PhysicalKey::Code(KeyCode::Digit0) => {
+                            self.selection.clear();
+                        }
+                        PhysicalKey::Code(KeyCode::KeyC) => {
+                            if let Some((min, max)) = self.selection.limited_bounds() {
+                                self.clipboard = Some(self.world.copy_region(min, max));
+                            }
+                        }
+                        PhysicalKey::Code(KeyCode::KeyP) => {
+                            let hit = self.look_hit();
+
+                            if let (Some(clipboard), Some(hit)) = (&self.clipboard, hit) {
+                                let edit = clipboard.paste_edit(hit.coord + hit.normal, false);
+                                self.world.apply_user_edit(&edit);
+                            }
+                        }
+                        PhysicalKey::Code(KeyCode::KeyK) => {
+                            if let Some(clipboard) = &self.clipboard {
+                                let schematic = Schematic::new(clipboard.clone());
+
+                                match schematic.save(Self::CLIPBOARD_FILE) {
+                                    Ok(()) => {
+                                        log::info!("Saved clipboard to {}", Self::CLIPBOARD_FILE)
+                                    }
+                                    Err(e) => log::error!("Failed to save clipboard: {e}"),
+                                }
+                            }
+                        }
+                        PhysicalKey::Code(KeyCode::KeyO) => {
+                            match Schematic::load(Self::CLIPBOARD_FILE) {
+                                Ok(schematic) => self.clipboard = Some(schematic.structure),
+                                Err(e) => log::error!("Failed to load clipboard: {e}"),
+                            }
+                        }
+                        PhysicalKey::Code(KeyCode::KeyV) => {
+                            if let Some(hit) = self.look_hit() {
+                                self.import_vox(hit.coord + hit.normal);
+                            }
+                        }
+                        PhysicalKey::Code(KeyCode::KeyU) => {
+                            if let Some((min, max)) = self.selection.limited_bounds() {
+                                let structure = self.world.copy_region(min, max);
+
+                                match generator::vox::save_vox(Self::VOX_EXPORT_FILE, &structure) {
+                                    Ok(()) => log::info!(
+                                        "Exported selection to {}",
+                                        Self::VOX_EXPORT_FILE
+                                    ),
+                                    Err(e) => log::error!("Failed to export selection: {e}"),
+                                }
+                            }
+                        }
+                        PhysicalKey::Code(KeyCode::KeyH) => {
+                            if let Some((min, max)) = self.selection.bounds() {
+                                self.export_meshes(min.into(), max.into());
+                            }
+                        }
+                        PhysicalKey::Code(KeyCode::KeyT) => {
+                            self.clipboard = self.clipboard.as_ref().map(Structure::rotated_y);
+                        }
+                        PhysicalKey::Code(KeyCode::KeyX) => {
+                            self.clipboard = self.clipboard.as_ref().map(Structure::mirrored_x);
+                        }
+                        PhysicalKey::Code(KeyCode::KeyJ) => {
+                            self.clipboard = self.clipboard.as_ref().map(Structure::mirrored_z);
+                        }
+                        PhysicalKey::Code(KeyCode::Tab) => {
+                            self.movement_mode = self.movement_mode.toggled();
+                            self.player = Player::from_eye(self.camera.eye);
+                        }
                         PhysicalKey::Code(KeyCode::ControlLeft) => {
                             if self.cursor_locked {
                                 self.window.set_cursor_visible(true);
@@ -0,0 +1,210 @@
+use cgmath::{EuclideanSpace, Point3, Vector3};
+
+use super::generator::{
+    chunk::WorldCoord,
+    query::Aabb,
+    voxel::{Blocks, Voxel},
+    World,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MovementMode {
+    /// Free camera movement through terrain
+    Fly,
+    /// Player body with gravity and collisions
+    Walk,
+}
+
+impl MovementMode {
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Fly => Self::Walk,
+            Self::Walk => Self::Fly,
+        }
+    }
+}
+
+pub struct Player {
+    /// Center of the bottom of the body
+    pub position: Point3<f32>,
+    pub velocity: Vector3<f32>,
+    pub on_ground: bool,
+    pub in_fluid: bool,
+}
+
+#[allow(dead_code)]
+impl Player {
+    pub const WIDTH: f32 = 0.6;
+    pub const HEIGHT: f32 = 1.8;
+    pub const EYE_HEIGHT: f32 = 1.62;
+    /// Obstacles up to this height are climbed without jumping
+    pub const STEP_HEIGHT: f32 = 1.0;
+    pub const WALK_SPEED: f32 = 4.5;
+    pub const JUMP_VELOCITY: f32 = 8.5;
+    pub const GRAVITY: f32 = 28.0;
+    pub const TERMINAL_VELOCITY: f32 = 60.0;
+
+    pub const SWIM_SPEED: f32 = 2.5;
+    pub const SWIM_UP_VELOCITY: f32 = 3.0;
+    pub const FLUID_GRAVITY: f32 = 6.0;
+    pub const FLUID_DRAG: f32 = 3.0;
+
+    /// Longest step simulated at once, longer frames are split
+    const MAX_STEP: f32 = 1.0 / 60.0;
+
+    pub fn new(position: Point3<f32>) -> Self {
+        Self {
+            position,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            on_ground: false,
+            in_fluid: false,
+        }
+    }
+
+    /// Places the body so that its eyes are at `eye`
+    pub fn from_eye(eye: Point3<f32>) -> Self {
+        Self::new(eye - Vector3::new(0.0, Self::EYE_HEIGHT, 0.0))
+    }
+
+    pub fn eye(&self) -> Point3<f32> {
+        self.position + Vector3::new(0.0, Self::EYE_HEIGHT, 0.0)
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        let half = Self::WIDTH / 2.0;
+        let position = self.position.to_vec();
+
+        Aabb::new(
+            position + Vector3::new(-half, 0.0, -half),
+            position + Vector3::new(half, Self::HEIGHT, half),
+        )
+    }
+
+    /// Advances the simulation.
+    ///
+    /// `wish_direction` is the horizontal direction the player wants to move in,
+    /// either normalized or zero, `jump` is whether jump/swim up is held.
+    pub fn update<T>(
+        &mut self,
+        world: &World<T>,
+        wish_direction: Vector3<f32>,
+        jump: bool,
+        delta: f32,
+    ) {
+        // Don't fall into chunks that haven't been generated yet
+        let feet: WorldCoord = self.position.to_vec().into();
+        if world.get_voxel(feet).is_none() {
+            self.velocity = Vector3::new(0.0, 0.0, 0.0);
+            return;
+        }
+
+        let steps = (delta / Self::MAX_STEP).ceil().max(1.0);
+        for _ in 0..steps as usize {
+            self.step(world, wish_direction, jump, delta / steps);
+        }
+    }
+
+    fn step<T>(&mut self, world: &World<T>, wish_direction: Vector3<f32>, jump: bool, delta: f32) {
+        self.in_fluid = world
+            .voxels_in_aabb(&self.aabb())
+            .iter()
+            .any(|(_, voxel)| is_fluid(*voxel));
+
+        if self.in_fluid {
+            let wish = wish_direction * Self::SWIM_SPEED;
+            self.velocity.x = wish.x;
+            self.velocity.z = wish.z;
+
+            self.velocity.y -= Self::FLUID_GRAVITY * delta;
+            self.velocity.y -= self.velocity.y * (Self::FLUID_DRAG * delta).min(1.0);
+
+            if jump {
+                self.velocity.y = Self::SWIM_UP_VELOCITY;
+            }
+        } else {
+            let wish = wish_direction * Self::WALK_SPEED;
+            self.velocity.x = wish.x;
+            self.velocity.z = wish.z;
+
+            self.velocity.y =
+                (self.velocity.y - Self::GRAVITY * delta).max(-Self::TERMINAL_VELOCITY);
+
+            if jump && self.on_ground {
+                self.velocity.y = Self::JUMP_VELOCITY;
+            }
+        }
+
+        let wanted = self.velocity * delta;
+        let mut moved = self.move_clipped(world, self.aabb(), wanted);
+
+        // Try climbing over the obstacle when walking into it
+        let blocked_horizontally = moved.x != wanted.x || moved.z != wanted.z;
+        if blocked_horizontally && (self.on_ground || self.in_fluid) {
+            let up = Vector3::new(0.0, Self::STEP_HEIGHT, 0.0);
+            let raised = self.move_clipped(world, self.aabb(), up);
+            let stepped = self.move_clipped(
+                world,
+                self.aabb().translated(raised),
+                Vector3::new(wanted.x, 0.0, wanted.z),
+            );
+            let down = self.move_clipped(
+                world,
+                self.aabb().translated(raised + stepped),
+                Vector3::new(0.0, -raised.y + wanted.y.min(0.0), 0.0),
+            );
+
+            let stepped_distance = stepped.x * stepped.x + stepped.z * stepped.z;
+            let moved_distance = moved.x * moved.x + moved.z * moved.z;
+
+            if stepped_distance > moved_distance {
+                moved = raised + stepped + down;
+            }
+        }
+
+        self.on_ground = wanted.y < 0.0 && moved.y > wanted.y;
+
+        if moved.x != wanted.x {
+            self.velocity.x = 0.0;
+        }
+        if moved.y != wanted.y {
+            self.velocity.y = 0.0;
+        }
+        if moved.z != wanted.z {
+            self.velocity.z = 0.0;
+        }
+
+        self.position += moved;
+    }
+
+    /// Sweeps `aabb` by `offset` one axis at a time (Y first),
+    /// returning how far it could actually move.
+    fn move_clipped<T>(&self, world: &World<T>, aabb: Aabb, offset: Vector3<f32>) -> Vector3<f32> {
+        // Slightly inflated so that voxels we're resting against are always found
+        let obstacles: Vec<Aabb> = world
+            .solid_voxels_in_aabb(&aabb.expanded_towards(offset).inflated(0.001))
+            .into_iter()
+            .map(|(coord, _)| Aabb::from_voxel(coord))
+            .collect();
+
+        let mut aabb = aabb;
+        let mut moved = Vector3::new(0.0, 0.0, 0.0);
+
+        for axis in [1, 0, 2] {
+            let clipped = obstacles.iter().fold(offset[axis], |o, obstacle| {
+                obstacle.clip_offset(&aabb, axis, o)
+            });
+
+            let mut translation = Vector3::new(0.0, 0.0, 0.0);
+            translation[axis] = clipped;
+
+            aabb = aabb.translated(translation);
+            moved[axis] = clipped;
+        }
+
+        moved
+    }
+}
+
+fn is_fluid(voxel: Voxel) -> bool {
+    Blocks::BLOCKS[voxel.id as usize].fluid
+}
@@ -0,0 +1,144 @@
+use super::{
+    debug::{DebugDrawer, ModelName},
+    generator::{
+        chunk::WorldCoord,
+        edit::WorldEdit,
+        voxel::{Blocks, Voxel},
+        World,
+    },
+};
+
+/// Box between two corner voxels picked by the user
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Selection {
+    pub first: Option<WorldCoord>,
+    pub second: Option<WorldCoord>,
+}
+
+#[allow(dead_code)]
+impl Selection {
+    /// Largest selection edited or copied at once, 128 blocks on each side
+    pub const MAX_VOLUME: u64 = 128 * 128 * 128;
+
+    pub fn clear(&mut self) {
+        self.first = None;
+        self.second = None;
+    }
+
+    /// Inclusive minimum and maximum corners, once both are set
+    pub fn bounds(&self) -> Option<(WorldCoord, WorldCoord)> {
+        let (a, b) = (self.first?, self.second?);
+
+        Some((
+            WorldCoord {
+                x: a.x.min(b.x),
+                y: a.y.min(b.y),
+                z: a.z.min(b.z),
+            },
+            WorldCoord {
+                x: a.x.max(b.x),
+                y: a.y.max(b.y),
+                z: a.z.max(b.z),
+            },
+        ))
+    }
+
+    /// Number of voxels in the selection, once both corners are set
+    pub fn volume(&self) -> Option<u64> {
+        let (min, max) = self.bounds()?;
+        let side = |min: i32, max: i32| (max as i64 - min as i64 + 1) as u64;
+
+        Some(side(min.x, max.x) * side(min.y, max.y) * side(min.z, max.z))
+    }
+
+    /// Like `bounds`, refusing selections larger than `MAX_VOLUME`
+    pub fn limited_bounds(&self) -> Option<(WorldCoord, WorldCoord)> {
+        let volume = self.volume()?;
+
+        if volume > Self::MAX_VOLUME {
+            log::warn!(
+                "Selection of {volume} voxels is larger than the limit of {}",
+                Self::MAX_VOLUME
+            );
+            return None;
+        }
+
+        self.bounds()
+    }
+
+    /// Sets every voxel in the selection to `voxel`
+    pub fn fill_edit(&self, voxel: Voxel) -> Option<WorldEdit> {
+        let (min, max) = self.limited_bounds()?;
+        let mut edit = WorldEdit::new();
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    edit.set(WorldCoord { x, y, z }, voxel);
+                }
+            }
+        }
+
+        Some(edit)
+    }
+
+    /// Swaps every `from` voxel in the selection for `to`
+    pub fn replace_edit<T>(&self, world: &World<T>, from: Voxel, to: Voxel) -> Option<WorldEdit> {
+        let (min, max) = self.limited_bounds()?;
+        let mut edit = WorldEdit::new();
+
+        world.for_each_voxel_in_region(min, max, |coord, voxel| {
+            if voxel == from {
+                edit.set(coord, to);
+            }
+        });
+
+        Some(edit)
+    }
+
+    /// Clears everything but the outer shell of the selection
+    pub fn hollow_edit(&self) -> Option<WorldEdit> {
+        let (min, max) = self.limited_bounds()?;
+        let mut edit = WorldEdit::new();
+
+        for x in min.x + 1..max.x {
+            for y in min.y + 1..max.y {
+                for z in min.z + 1..max.z {
+                    edit.set(WorldCoord { x, y, z }, Blocks::AIR.default_state());
+                }
+            }
+        }
+
+        Some(edit)
+    }
+
+    pub fn append_debug(&self, debug: &mut DebugDrawer) {
+        let corner_color = cgmath::Vector4::new(1.0, 0.6, 0.0, 1.0);
+
+        for corner in [self.first, self.second].into_iter().flatten() {
+            let position: cgmath::Vector3<f32> = corner.into();
+
+            debug.append_mesh(
+                ModelName::Cube,
+                position - cgmath::Vector3::new(0.02, 0.02, 0.02),
+                cgmath::Vector3::new(1.04, 1.04, 1.04),
+                corner_color,
+            );
+        }
+
+        if let Some((min, max)) = self.bounds() {
+            let size = max - min;
+
+            debug.append_mesh(
+                ModelName::Cube,
+                min.into(),
+                cgmath::Vector3::new(
+                    size.x as f32 + 1.0,
+                    size.y as f32 + 1.0,
+                    size.z as f32 + 1.0,
+                ),
+                cgmath::Vector4::new(1.0, 0.8, 0.0, 1.0),
+            );
+        }
+    }
+}
@@ -1,13 +1,15 @@
+// Packed as described on `ChunkVertex`
 struct VertexInput {
-    @location(0) position: vec3<f32>,
-    @location(1) normal: vec3<f32>,
-    @location(2) uv: vec2<f32>,
+    @location(0) position: u32,
+    @location(1) data: u32,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) normal: vec3<f32>,
     @location(1) texCoord: vec2<f32>,
+    @location(2) @interpolate(flat) layer: u32,
+    @location(3) light: f32,
 };
 
 struct Camera {
@@ -16,34 +18,66 @@ struct Camera {
     projection: mat4x4<f32>,
 };
 
-struct Model {
-    model: mat4x4<f32>,
+// `ChunkData` of every chunk, indexed by the instance index
+struct Chunk {
+    origin: vec3<f32>,
+    vertex_offset: u32,
+    index_offset: u32,
+    index_count: u32,
 }
 
 @group(0) @binding(0)
-var<uniform> model: Model;
+var<storage, read> chunks: array<Chunk>;
 
 @group(2) @binding(0)
 var<uniform> camera: Camera;
 
+const SUBDIVISIONS: f32 = 16.0;
+
+const NORMALS = array<vec3<f32>, 6>(
+    vec3(-1.0, 0.0, 0.0),
+    vec3(1.0, 0.0, 0.0),
+    vec3(0.0, 1.0, 0.0),
+    vec3(0.0, -1.0, 0.0),
+    vec3(0.0, 0.0, 1.0),
+    vec3(0.0, 0.0, -1.0),
+);
+
 @vertex
-fn vs_main(in: VertexInput) -> VertexOutput {
+fn vs_main(in: VertexInput, @builtin(instance_index) chunk: u32) -> VertexOutput {
+    let position = vec3(
+        f32(extractBits(in.position, 0u, 10u)),
+        f32(extractBits(in.position, 10u, 10u)),
+        f32(extractBits(in.position, 20u, 10u)),
+    ) / SUBDIVISIONS;
+    let uv = vec2(
+        f32(extractBits(in.data, 3u, 5u)),
+        f32(extractBits(in.data, 8u, 5u)),
+    ) / SUBDIVISIONS;
+
     var out: VertexOutput;
-    out.clip_position = camera.projection * camera.view * model.model * vec4(in.position, 1.0);
-    out.normal = in.normal;
-    out.texCoord = in.uv;
+    out.clip_position = camera.projection * camera.view * vec4(chunks[chunk].origin + position, 1.0);
+    out.normal = NORMALS[extractBits(in.data, 0u, 3u)];
+    out.texCoord = uv;
+    out.layer = extractBits(in.data, 13u, 11u);
+    out.light = f32(extractBits(in.data, 24u, 8u)) / 255.0;
     return out;
 }
 
 @group(1) @binding(0)
-var t_diffuse: texture_2d<f32>;
+var t_diffuse: texture_2d_array<f32>;
 @group(1) @binding(1)
 var t_sampler: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let sun = normalize(vec3(2.0, 3.0, 1.0));
-    let texture_sample = textureSample(t_diffuse, t_sampler, in.texCoord);
+    let texture_sample = textureSample(t_diffuse, t_sampler, in.texCoord, in.layer);
+
+    // Cutout textures such as leaves and plants
+    if texture_sample.a < 0.5 {
+        discard;
+    }
 
-    return vec4(texture_sample.rgb * clamp(dot(sun, in.normal), 0.2, 1.0), 1.0);
+    return vec4(texture_sample.rgb * clamp(dot(sun, in.normal), 0.2, 1.0) * in.light, 1.0);
 }
@@ -1,6 +1,8 @@
 #[cfg(test)]
 #[allow(unused_imports)]
-use super::generator::chunk::{ChunkCoord, ChunkLocalCoord, WorldCoord, CHUNK_SIZE};
+use super::generator::chunk::{
+    BlockOffsetCoord, ChunkCoord, ChunkLocalCoord, WorldCoord, CHUNK_SIZE,
+};
 
 #[test]
 fn positive_negative_test() {
@@ -58,3 +60,1519 @@ fn coord_test() {
         assert_eq!(local_coord, expected_local);
     }
 }
+
+#[cfg(test)]
+fn test_world() -> super::generator::World<()> {
+    use super::generator::{chunk::Chunk, World};
+
+    let mut world = World::new(());
+    world.insert_chunk(Box::new(Chunk::new(ChunkCoord { x: 0, y: 0, z: 0 })));
+    world.insert_chunk(Box::new(Chunk::new(ChunkCoord { x: -1, y: 0, z: 0 })));
+    world
+}
+
+/// `test_world` with every voxel of `blocks` set
+#[cfg(test)]
+fn world_with(
+    blocks: &[(WorldCoord, super::generator::voxel::Voxel)],
+) -> super::generator::World<()> {
+    let mut world = test_world();
+    for (coord, voxel) in blocks {
+        world.set_voxel(*coord, *voxel);
+    }
+    world
+}
+
+/// Mesh of the chunk at the origin with `blocks` set, empty if it has no faces
+#[cfg(test)]
+fn origin_mesh(
+    blocks: &[(WorldCoord, super::generator::voxel::Voxel)],
+) -> super::mesh::MeshInfo<super::mesh::Vertex3d> {
+    let origin = ChunkCoord { x: 0, y: 0, z: 0 };
+
+    world_with(blocks)
+        .chunk_meshes(origin, origin)
+        .pop()
+        .map(|(_, mesh)| mesh)
+        .unwrap_or_default()
+}
+
+#[test]
+fn ray_hit_test() {
+    use super::generator::{raycast::RayFilter, voxel::Blocks, Ray};
+
+    let mut world = test_world();
+    world.set_voxel(
+        WorldCoord { x: 5, y: 2, z: 3 },
+        Blocks::STONE.default_state(),
+    );
+
+    // Hits the -X face of the block
+    let hit = world
+        .ray_hit(
+            Ray {
+                origin: cgmath::Point3::new(0.5, 2.5, 3.5),
+                direction: cgmath::Vector3::new(1.0, 0.0, 0.0),
+            },
+            64.0,
+            RayFilter::Solid,
+            None,
+        )
+        .expect("Ray should hit the block");
+
+    assert_eq!(hit.coord, WorldCoord { x: 5, y: 2, z: 3 });
+    assert_eq!(hit.normal, BlockOffsetCoord { x: -1, y: 0, z: 0 });
+    assert!((hit.distance - 4.5).abs() < 1e-5);
+    assert!((hit.point.x - 5.0).abs() < 1e-5);
+
+    // Coming from above through a chunk boundary hits the top face
+    let hit = world
+        .ray_hit(
+            Ray {
+                origin: cgmath::Point3::new(-1.5, 10.0, 3.5),
+                direction: cgmath::Vector3::new(8.0, -8.0, 0.0),
+            },
+            64.0,
+            RayFilter::Solid,
+            None,
+        )
+        .expect("Ray should hit the block");
+
+    assert_eq!(hit.coord, WorldCoord { x: 5, y: 2, z: 3 });
+    assert_eq!(hit.normal, BlockOffsetCoord { x: 0, y: 1, z: 0 });
+
+    // A ray that only grazes the corner of the block still hits it
+    let hit = world.ray_hit(
+        Ray {
+            origin: cgmath::Point3::new(0.0, 2.0, 0.01),
+            direction: cgmath::Vector3::new(5.0, 0.5, 3.0),
+        },
+        64.0,
+        RayFilter::Solid,
+        None,
+    );
+
+    assert_eq!(hit.map(|h| h.coord), Some(WorldCoord { x: 5, y: 2, z: 3 }));
+
+    // Out of range
+    let hit = world.ray_hit(
+        Ray {
+            origin: cgmath::Point3::new(0.5, 2.5, 3.5),
+            direction: cgmath::Vector3::new(1.0, 0.0, 0.0),
+        },
+        4.0,
+        RayFilter::Solid,
+        None,
+    );
+
+    assert!(hit.is_none());
+}
+
+#[test]
+fn spatial_query_test() {
+    use super::generator::{query::Aabb, voxel::Blocks};
+
+    let mut world = test_world();
+    let stone = Blocks::STONE.default_state();
+
+    // Straddles the boundary between chunks -1 and 0
+    world.set_voxel(WorldCoord { x: -1, y: 4, z: 4 }, stone);
+    world.set_voxel(WorldCoord { x: 0, y: 4, z: 4 }, stone);
+    world.set_voxel(WorldCoord { x: 0, y: 9, z: 4 }, stone);
+
+    let found = world.solid_voxels_in_aabb(&Aabb::new(
+        cgmath::Vector3::new(-1.5, 3.5, 3.5),
+        cgmath::Vector3::new(0.5, 4.5, 4.5),
+    ));
+    assert_eq!(found.len(), 2);
+
+    // Touching a voxel is not overlapping it
+    let found = world.solid_voxels_in_aabb(&Aabb::new(
+        cgmath::Vector3::new(1.0, 4.0, 4.0),
+        cgmath::Vector3::new(2.0, 5.0, 5.0),
+    ));
+    assert!(found.is_empty());
+
+    let found = world.solid_voxels_in_sphere(cgmath::Point3::new(0.0, 4.5, 4.5), 1.0);
+    assert_eq!(found.len(), 2);
+
+    // Region spanning an unloaded chunk only visits loaded voxels
+    let mut visited = 0;
+    world.for_each_voxel_in_region(
+        WorldCoord { x: 0, y: 0, z: 0 },
+        WorldCoord {
+            x: 1,
+            y: CHUNK_SIZE as i32,
+            z: 0,
+        },
+        |_, _| visited += 1,
+    );
+    assert_eq!(visited, 2 * CHUNK_SIZE);
+
+    assert_eq!(world.column_height(0, 4), Some(9));
+    assert_eq!(world.column_height(-1, 4), Some(4));
+    assert_eq!(world.column_height(1, 4), None);
+    assert_eq!(world.column_height(100, 4), None);
+}
+
+#[test]
+fn player_collision_test() {
+    use super::{generator::voxel::Blocks, player::Player};
+
+    let mut world = test_world();
+    let stone = Blocks::STONE.default_state();
+
+    for x in 0..CHUNK_SIZE as i32 {
+        for z in 0..8 {
+            world.set_voxel(WorldCoord { x, y: 0, z }, stone);
+        }
+    }
+
+    // One block step and a two block wall further along
+    for z in 0..8 {
+        for x in 6..9 {
+            world.set_voxel(WorldCoord { x, y: 1, z }, stone);
+        }
+        world.set_voxel(WorldCoord { x: 12, y: 1, z }, stone);
+        world.set_voxel(WorldCoord { x: 12, y: 2, z }, stone);
+    }
+
+    let mut player = Player::new(cgmath::Point3::new(2.5, 5.0, 4.5));
+    let no_input = cgmath::Vector3::new(0.0, 0.0, 0.0);
+
+    player.update(&world, no_input, false, 2.0);
+    assert!(player.on_ground);
+    assert!((player.position.y - 1.0).abs() < 1e-4);
+
+    let east = cgmath::Vector3::new(1.0, 0.0, 0.0);
+    player.update(&world, east, false, 1.0);
+    assert!((player.position.y - 2.0).abs() < 1e-4);
+
+    player.update(&world, east, false, 2.0);
+    assert!((player.position.y - 1.0).abs() < 1e-4);
+    assert!((player.position.x - (12.0 - Player::WIDTH / 2.0)).abs() < 1e-4);
+}
+
+#[test]
+fn world_edit_test() {
+    use super::generator::{edit::WorldEdit, voxel::Blocks};
+
+    let mut world = test_world();
+    let stone = Blocks::STONE.default_state();
+    let air = Blocks::AIR.default_state();
+
+    let mut edit = WorldEdit::new();
+    edit.set(WorldCoord { x: 0, y: 5, z: 5 }, stone);
+    edit.set(WorldCoord { x: -1, y: 5, z: 5 }, stone);
+    edit.set(WorldCoord { x: 3, y: 5, z: 5 }, stone);
+    // Not loaded, dropped
+    edit.set(WorldCoord { x: 100, y: 5, z: 5 }, stone);
+
+    let revert = world.apply_edit(&edit);
+
+    assert_eq!(revert.len(), 3);
+    assert_eq!(
+        world.get_voxel(WorldCoord { x: -1, y: 5, z: 5 }),
+        Some(stone)
+    );
+    assert_eq!(
+        world.get_voxel(WorldCoord { x: 3, y: 5, z: 5 }),
+        Some(stone)
+    );
+    // Both chunks are queued for remeshing once
+    assert_eq!(world.meshgen_queue_count(), 2);
+
+    let redo = world.apply_edit(&revert);
+
+    assert_eq!(world.get_voxel(WorldCoord { x: 0, y: 5, z: 5 }), Some(air));
+    assert_eq!(world.get_voxel(WorldCoord { x: -1, y: 5, z: 5 }), Some(air));
+    assert_eq!(redo.get(WorldCoord { x: 3, y: 5, z: 5 }), Some(stone));
+    assert_eq!(world.meshgen_queue_count(), 2);
+}
+
+#[test]
+fn edit_history_test() {
+    use super::generator::{edit::WorldEdit, voxel::Blocks};
+
+    let mut world = test_world();
+    let stone = Blocks::STONE.default_state();
+    let dirt = Blocks::DIRT_BLOCK.default_state();
+    let air = Blocks::AIR.default_state();
+    let coord = WorldCoord { x: 2, y: 2, z: 2 };
+
+    let mut edit = WorldEdit::new();
+    edit.set(coord, stone);
+    world.apply_user_edit(&edit);
+
+    let mut edit = WorldEdit::new();
+    edit.set(coord, dirt);
+    world.apply_user_edit(&edit);
+
+    // No-op edits aren't recorded
+    world.apply_user_edit(&edit);
+    assert_eq!(world.history().undo_count(), 2);
+
+    assert!(world.undo());
+    assert_eq!(world.get_voxel(coord), Some(stone));
+    assert!(world.undo());
+    assert_eq!(world.get_voxel(coord), Some(air));
+    assert!(!world.undo());
+
+    assert!(world.redo());
+    assert_eq!(world.get_voxel(coord), Some(stone));
+
+    // New edits clear the redo stack
+    world.apply_user_edit(&WorldEdit::sphere(coord, 1, air));
+    assert_eq!(world.get_voxel(coord), Some(air));
+    assert!(!world.redo());
+    assert!(world.undo());
+    assert_eq!(world.get_voxel(coord), Some(stone));
+}
+
+#[test]
+fn brush_test() {
+    use super::{
+        brush::{Brush, BrushMode, BrushShape},
+        generator::voxel::Blocks,
+    };
+
+    let mut world = test_world();
+    let stone = Blocks::STONE.default_state();
+    let dirt = Blocks::DIRT_BLOCK.default_state();
+    let center = WorldCoord { x: 8, y: 8, z: 8 };
+
+    let mut brush = Brush::default();
+    brush.shape = BrushShape::Cube;
+    brush.mode = BrushMode::Place;
+    brush.radius = 1;
+    brush.block = stone;
+    brush.target = stone;
+
+    let edit = brush.edit(&world, center);
+    assert_eq!(edit.len(), 27);
+    // Cached previews are recomputed once the world was edited
+    let revision = world.revision();
+    world.apply_edit(&edit);
+    assert!(world.revision() > revision);
+
+    // Only the center isn't on the surface of the cube
+    brush.mode = BrushMode::Paint;
+    brush.block = dirt;
+    let edit = brush.edit(&world, center);
+    assert_eq!(edit.len(), 26);
+    assert!(edit.get(center).is_none());
+
+    brush.mode = BrushMode::Replace;
+    brush.shape = BrushShape::Disk;
+    let edit = brush.edit(&world, center);
+    assert_eq!(edit.len(), 5);
+
+    // A single floating block is smoothed away
+    let mut world = test_world();
+    world.set_voxel(center, stone);
+    brush.mode = BrushMode::Smooth;
+    let edit = brush.edit(&world, center);
+    assert_eq!(edit.get(center), Some(Blocks::AIR.default_state()));
+}
+
+#[test]
+fn selection_test() {
+    use super::{generator::voxel::Blocks, selection::Selection};
+
+    let mut world = test_world();
+    let stone = Blocks::STONE.default_state();
+    let dirt = Blocks::DIRT_BLOCK.default_state();
+    let air = Blocks::AIR.default_state();
+
+    // Corners in any order, spanning the chunk border at x = 0
+    let selection = Selection {
+        first: Some(WorldCoord { x: 1, y: 4, z: 3 }),
+        second: Some(WorldCoord { x: -2, y: 2, z: 5 }),
+    };
+    let (min, max) = selection.bounds().unwrap();
+    assert_eq!(min, WorldCoord { x: -2, y: 2, z: 3 });
+    assert_eq!(max, WorldCoord { x: 1, y: 4, z: 5 });
+
+    world.apply_edit(&selection.fill_edit(stone).unwrap());
+    let copied = world.copy_region(min, max);
+    assert_eq!(copied.voxels().iter().filter(|v| **v == stone).count(), 36);
+
+    let edit = selection.hollow_edit().unwrap();
+    assert_eq!(edit.len(), 2);
+    world.apply_edit(&edit);
+    assert_eq!(world.get_voxel(WorldCoord { x: 0, y: 3, z: 4 }), Some(air));
+
+    let edit = selection.replace_edit(&world, stone, dirt).unwrap();
+    assert_eq!(edit.len(), 34);
+
+    // Too large to edit on the main thread
+    let huge = Selection {
+        first: Some(WorldCoord { x: 0, y: 0, z: 0 }),
+        second: Some(WorldCoord {
+            x: 128,
+            y: 127,
+            z: 127,
+        }),
+    };
+    assert_eq!(huge.volume(), Some(Selection::MAX_VOLUME + 128 * 128));
+    assert!(huge.fill_edit(stone).is_none());
+    assert!(huge.hollow_edit().is_none());
+
+    // Copy an L shape and paste it rotated
+    let mut world = test_world();
+    world.set_voxel(WorldCoord { x: 2, y: 2, z: 2 }, stone);
+    world.set_voxel(WorldCoord { x: 3, y: 2, z: 2 }, dirt);
+    world.set_voxel(WorldCoord { x: 2, y: 2, z: 3 }, dirt);
+
+    let structure = world.copy_region(
+        WorldCoord { x: 2, y: 2, z: 2 },
+        WorldCoord { x: 4, y: 2, z: 3 },
+    );
+    assert_eq!(structure.size(), [3, 1, 2]);
+
+    let rotated = structure.rotated_y();
+    assert_eq!(rotated.size(), [2, 1, 3]);
+    assert_eq!(rotated.get(1, 0, 0), Some(stone));
+    assert_eq!(rotated.get(1, 0, 1), Some(dirt));
+    assert_eq!(rotated.get(0, 0, 0), Some(dirt));
+    assert_eq!(rotated.rotated_y().rotated_y().rotated_y(), structure);
+    assert_eq!(structure.mirrored_x().mirrored_x(), structure);
+    assert_eq!(structure.mirrored_x().get(2, 0, 0), Some(stone));
+
+    let origin = WorldCoord { x: 8, y: 8, z: 8 };
+    let edit = rotated.paste_edit(origin, false);
+    assert_eq!(edit.len(), 3);
+    world.apply_edit(&edit);
+    assert_eq!(
+        world.get_voxel(WorldCoord { x: 9, y: 8, z: 8 }),
+        Some(stone)
+    );
+}
+
+#[test]
+fn schematic_test() {
+    use super::generator::{
+        schematic::Schematic,
+        structure::Structure,
+        voxel::{Blocks, RegisteredBlock},
+    };
+    use std::io::Write;
+
+    // Three blocks need two bits per voxel
+    let mut structure = Structure::new([5, 3, 7]);
+    let blocks: [RegisteredBlock; 3] = [Blocks::STONE, Blocks::GRASS_BLOCK, Blocks::LOG];
+    for (i, block) in blocks.iter().enumerate() {
+        structure.set(i, i, i + 2, block.default_state());
+    }
+
+    let mut schematic = Schematic::new(structure);
+    schematic
+        .metadata
+        .insert("name".to_string(), "test".to_string());
+
+    let loaded = Schematic::from_bytes(&schematic.to_bytes()).unwrap();
+    assert_eq!(loaded, schematic);
+
+    let truncated = schematic.to_bytes();
+    assert!(Schematic::from_bytes(&truncated[..truncated.len() / 2]).is_err());
+
+    // Sponge schematic version 2, 2x1x2
+    fn named(data: &mut Vec<u8>, id: u8, name: &str) {
+        data.push(id);
+        data.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        data.extend_from_slice(name.as_bytes());
+    }
+
+    let sponge = |[width, height, length]: [i16; 3]| {
+        let mut nbt = Vec::new();
+        named(&mut nbt, 10, "Schematic");
+        named(&mut nbt, 3, "Version");
+        nbt.extend_from_slice(&2i32.to_be_bytes());
+        for (name, value) in [("Width", width), ("Height", height), ("Length", length)] {
+            named(&mut nbt, 2, name);
+            nbt.extend_from_slice(&value.to_be_bytes());
+        }
+        named(&mut nbt, 10, "Palette");
+        for (state, index) in [
+            ("minecraft:air", 0i32),
+            ("minecraft:oak_log[axis=y]", 1),
+            ("minecraft:cobblestone", 2),
+            ("minecraft:diamond_block", 3),
+        ] {
+            named(&mut nbt, 3, state);
+            nbt.extend_from_slice(&index.to_be_bytes());
+        }
+        nbt.push(0);
+        named(&mut nbt, 7, "BlockData");
+        nbt.extend_from_slice(&4i32.to_be_bytes());
+        // Index is x + z * width + y * width * length
+        nbt.extend_from_slice(&[1, 2, 3, 0]);
+        nbt.push(0);
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&nbt).unwrap();
+        encoder.finish().unwrap()
+    };
+
+    // 65535 on every side, refused before allocating it
+    assert!(Schematic::from_bytes(&sponge([-1, -1, -1])).is_err());
+
+    let imported = Schematic::from_bytes(&sponge([2, 1, 2])).unwrap();
+    let structure = imported.structure;
+    assert_eq!(structure.size(), [2, 1, 2]);
+    assert_eq!(structure.get(0, 0, 0), Some(Blocks::LOG.default_state()));
+    assert_eq!(structure.get(1, 0, 0), Some(Blocks::STONE.default_state()));
+    assert_eq!(structure.get(0, 0, 1), Some(Blocks::AIR.default_state()));
+    assert_eq!(structure.get(1, 0, 1), Some(Blocks::AIR.default_state()));
+}
+
+#[test]
+fn structure_feature_test() {
+    use super::generator::{
+        chunk::Chunk,
+        feature::{Feature, StructureFeature},
+        structure::Structure,
+        voxel::Blocks,
+    };
+    use std::collections::BTreeSet;
+
+    let stone = Blocks::STONE.default_state();
+    let mut structure = Structure::new([40, 2, 1]);
+    for x in 0..40 {
+        structure.set(x, 1, 0, stone);
+    }
+
+    // A copy in every cell, each wider than a chunk
+    let feature = StructureFeature::new(structure, 128, 1.0, 7);
+    let surface = |_: i32, _: i32| 4;
+
+    // Chunks covering x in -256..256 and z in 0..128, generated in any order
+    let mut placed = BTreeSet::new();
+    for z in [3, 0, 2, 1] {
+        for x in (-8..8).rev() {
+            let mut chunk = Chunk::new(ChunkCoord { x, y: 0, z });
+            feature.place(&mut chunk, 1234, &surface);
+
+            for (i, voxel) in chunk.chunk_data.iter().enumerate() {
+                if *voxel == stone {
+                    let local = BlockOffsetCoord {
+                        x: (i % CHUNK_SIZE) as i32,
+                        y: (i / CHUNK_SIZE % CHUNK_SIZE) as i32,
+                        z: (i / (CHUNK_SIZE * CHUNK_SIZE)) as i32,
+                    };
+                    let world = WorldCoord::from_chunk_and_local(chunk.coord, local);
+                    placed.insert((world.z, world.y, world.x));
+                }
+            }
+        }
+    }
+
+    // Copies split across chunk borders come out whole, except at the edges
+    let mut runs = Vec::new();
+    for (z, y, x) in placed.iter().copied() {
+        match runs.last_mut() {
+            Some((rz, ry, _, end)) if *rz == z && *ry == y && *end + 1 == x => *end = x,
+            _ => runs.push((z, y, x, x)),
+        }
+    }
+
+    let inner: Vec<_> = runs
+        .iter()
+        .filter(|(_, y, start, end)| *y == 6 && *start > -256 && *end < 255)
+        .collect();
+    assert!(!inner.is_empty());
+    assert!(inner
+        .iter()
+        .all(|(_, _, start, end)| (end - start + 1) % 40 == 0));
+    assert!(runs.iter().all(|(_, y, _, _)| *y == 6));
+}
+
+#[test]
+fn vox_test() {
+    use super::generator::{
+        structure::Structure,
+        vox::{read_vox, write_vox, VoxMapping},
+        voxel::Blocks,
+    };
+
+    let mut structure = Structure::new([3, 4, 2]);
+    structure.set(0, 0, 0, Blocks::STONE.default_state());
+    structure.set(2, 0, 1, Blocks::SAND.default_state());
+    structure.set(1, 3, 0, Blocks::GRASS_BLOCK.default_state());
+    structure.set(1, 2, 1, Blocks::LOG.default_state());
+
+    let data = write_vox(&structure).unwrap();
+    assert_eq!(read_vox(&data, &VoxMapping::new()).unwrap(), structure);
+
+    // Every block comes back, even ones sharing a color or not matched by color
+    let mut every_block = Structure::new([Blocks::BLOCKS.len(), 1, 1]);
+    for (x, block) in Blocks::BLOCKS.iter().enumerate() {
+        every_block.set(x, 0, 0, block.default_state());
+    }
+    let imported = read_vox(&write_vox(&every_block).unwrap(), &VoxMapping::new()).unwrap();
+    for (x, block) in Blocks::BLOCKS.iter().enumerate() {
+        assert_eq!(
+            imported.get(x, 0, 0),
+            Some(block.default_state()),
+            "{}",
+            block.name
+        );
+    }
+
+    // Mapped indices win over the nearest color
+    let mapping = VoxMapping::parse("# stone to dirt\n1 = dirt\n").unwrap();
+    let imported = read_vox(&data, &mapping).unwrap();
+    assert_eq!(
+        imported.get(0, 0, 0),
+        Some(Blocks::DIRT_BLOCK.default_state())
+    );
+    assert_eq!(imported.get(2, 0, 1), Some(Blocks::SAND.default_state()));
+
+    assert!(VoxMapping::parse("300 = Stone").is_err());
+    assert!(VoxMapping::parse("1 = Diamond").is_err());
+    assert!(read_vox(&data[..data.len() - 10], &VoxMapping::new()).is_err());
+    assert!(write_vox(&Structure::new([257, 1, 1])).is_err());
+
+    // Hand written 2x1x1 model with palette colors close to sand and grass
+    fn chunk(data: &mut Vec<u8>, id: &[u8; 4], content: &[u8]) {
+        data.extend_from_slice(id);
+        data.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(content);
+    }
+
+    let mut children = Vec::new();
+    chunk(
+        &mut children,
+        b"SIZE",
+        &[2, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0],
+    );
+    chunk(&mut children, b"nTRN", &[0; 8]);
+    chunk(
+        &mut children,
+        b"XYZI",
+        &[2, 0, 0, 0, 0, 0, 0, 7, 1, 0, 0, 200],
+    );
+    let mut rgba = vec![0; 256 * 4];
+    rgba[6 * 4..7 * 4].copy_from_slice(&[230, 210, 160, 255]);
+    rgba[199 * 4..200 * 4].copy_from_slice(&[90, 170, 40, 255]);
+    chunk(&mut children, b"RGBA", &rgba);
+
+    let mut file = b"VOX ".to_vec();
+    file.extend_from_slice(&200u32.to_le_bytes());
+    file.extend_from_slice(b"MAIN");
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&(children.len() as u32).to_le_bytes());
+    file.extend_from_slice(&children);
+
+    let imported = read_vox(&file, &VoxMapping::new()).unwrap();
+    assert_eq!(imported.size(), [2, 1, 1]);
+    assert_eq!(imported.get(0, 0, 0), Some(Blocks::SAND.default_state()));
+    assert_eq!(
+        imported.get(1, 0, 0),
+        Some(Blocks::GRASS_BLOCK.default_state())
+    );
+}
+
+#[test]
+fn mesh_export_test() {
+    use super::{
+        export::{gltf::write_glb, obj::write_obj},
+        generator::voxel::Blocks,
+    };
+
+    let stone = Blocks::STONE.default_state();
+    let world = world_with(&[
+        (WorldCoord { x: 0, y: 0, z: 0 }, stone),
+        (WorldCoord { x: -1, y: 0, z: 0 }, stone),
+    ]);
+
+    let meshes = world.chunk_meshes(
+        ChunkCoord { x: -1, y: 0, z: 0 },
+        ChunkCoord { x: 0, y: 0, z: 0 },
+    );
+    assert_eq!(meshes.len(), 2);
+    assert_eq!(meshes[0].0, ChunkCoord { x: -1, y: 0, z: 0 });
+
+    // Faces between the two blocks are culled across the chunk border
+    let vertices: usize = meshes.iter().map(|(_, m)| m.vertices.len()).sum();
+    let triangles: usize = meshes.iter().map(|(_, m)| m.indices.len() / 3).sum();
+    assert_eq!(triangles, 20);
+
+    let obj = write_obj(&meshes, "world.mtl");
+    let count = |prefix: &str| obj.lines().filter(|l| l.starts_with(prefix)).count();
+    assert_eq!(count("o "), 2);
+    assert_eq!(count("v "), vertices);
+    assert_eq!(count("f "), triangles);
+    assert!(obj.lines().any(|l| l == "v -1 0 0"));
+
+    let glb = write_glb(&meshes, &[1, 2, 3]);
+    let word = |offset: usize| u32::from_le_bytes(glb[offset..offset + 4].try_into().unwrap());
+    assert_eq!(&glb[0..4], b"glTF");
+    assert_eq!(word(4), 2);
+    assert_eq!(word(8) as usize, glb.len());
+
+    let json_len = word(12) as usize;
+    assert_eq!(&glb[16..20], b"JSON");
+    let json = std::str::from_utf8(&glb[20..20 + json_len]).unwrap();
+    assert!(json.contains(r#""name":"chunk_-1_0_0","mesh":0,"translation":[-32,0,0]"#));
+
+    let bin_len = word(20 + json_len) as usize;
+    assert_eq!(&glb[24 + json_len..28 + json_len], b"BIN\0");
+    assert_eq!(28 + json_len + bin_len, glb.len());
+
+    // Positions, normals, UVs and indices per chunk, then the image
+    let expected = vertices * (12 + 12 + 8) + triangles * 12 + 3;
+    assert_eq!(bin_len, expected.next_multiple_of(4));
+}
+
+#[test]
+fn heightmap_generator_test() {
+    use super::generator::{chunk::Chunk, heightmap::HeightmapGenerator, voxel::Blocks, Generator};
+    use image::{ImageBuffer, Luma, Rgb, RgbImage};
+
+    // Left half at a quarter of the height, right half at full height
+    let heights = ImageBuffer::from_fn(4, 2, |x, _| Luma([if x < 2 { 16384u16 } else { 65535 }]));
+    let generator = HeightmapGenerator::new(heights.clone())
+        .with_scale(1.0, 20.0)
+        .with_origin(WorldCoord { x: 0, y: 2, z: 0 });
+
+    assert_eq!(generator.height(0, 0), Some(7));
+    assert_eq!(generator.height(3, 1), Some(22));
+    assert_eq!(generator.height(4, 0), None);
+    assert_eq!(generator.height(-1, 0), None);
+
+    let mut chunk = Chunk::new(ChunkCoord { x: 0, y: 0, z: 0 });
+    generator.generate(&mut chunk);
+
+    let column = |chunk: &Chunk, x: usize, z: usize| {
+        (0..CHUNK_SIZE)
+            .map(|y| chunk.get_voxel(ChunkLocalCoord { x, y, z }).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    let left = column(&chunk, 1, 1);
+    assert_eq!(left[7], Blocks::GRASS_BLOCK.default_state());
+    assert_eq!(left[4], Blocks::DIRT_BLOCK.default_state());
+    assert_eq!(left[3], Blocks::STONE.default_state());
+    assert_eq!(left[8], Blocks::AIR.default_state());
+    assert!(column(&chunk, 5, 0)
+        .iter()
+        .all(|v| *v == Blocks::AIR.default_state()));
+
+    // Twice as large, with sand painted on the right
+    let colors = RgbImage::from_fn(2, 1, |x, _| {
+        if x == 0 {
+            Rgb([90, 160, 50])
+        } else {
+            Rgb([220, 205, 160])
+        }
+    });
+    let generator = HeightmapGenerator::new(heights)
+        .with_scale(2.0, 20.0)
+        .with_colors(colors);
+
+    let mut chunk = Chunk::new(ChunkCoord { x: 0, y: 0, z: 0 });
+    generator.generate(&mut chunk);
+
+    assert_eq!(column(&chunk, 0, 3)[5], Blocks::GRASS_BLOCK.default_state());
+    let right = column(&chunk, 7, 3);
+    assert_eq!(right[20], Blocks::SAND.default_state());
+    assert_eq!(right[18], Blocks::SAND.default_state());
+    assert_eq!(right[10], Blocks::STONE.default_state());
+    assert!(column(&chunk, 8, 0)
+        .iter()
+        .all(|v| *v == Blocks::AIR.default_state()));
+
+    // Foliage colors still paint grass, never leaves or plants
+    assert_eq!(
+        Blocks::nearest_color([40, 150, 35]).name,
+        Blocks::GRASS_BLOCK.name
+    );
+    assert_eq!(
+        Blocks::nearest_color([200, 30, 25]).name,
+        Blocks::DIRT_BLOCK.name
+    );
+}
+
+#[test]
+fn flat_generators_test() {
+    use super::generator::{
+        chunk::Chunk,
+        flat::{DebugGenerator, SuperflatGenerator},
+        spec::GeneratorSpec,
+        voxel::Blocks,
+        Generator,
+    };
+
+    let layers = SuperflatGenerator::parse_layers("stone*2, dirt ,Grass Block").unwrap();
+    assert_eq!(layers.len(), 3);
+    assert!(SuperflatGenerator::parse_layers("stone*many").is_err());
+    assert!(SuperflatGenerator::parse_layers("diamond").is_err());
+
+    let generator = SuperflatGenerator::new(layers).with_base(-1);
+    let mut chunk = Chunk::new(ChunkCoord { x: 3, y: 0, z: -2 });
+    generator.generate(&mut chunk);
+
+    let at = |chunk: &Chunk, x, y, z| chunk.get_voxel(ChunkLocalCoord { x, y, z }).unwrap();
+    assert_eq!(at(&chunk, 4, 0, 9), Blocks::STONE.default_state());
+    assert_eq!(at(&chunk, 4, 1, 9), Blocks::DIRT_BLOCK.default_state());
+    assert_eq!(at(&chunk, 4, 2, 9), Blocks::GRASS_BLOCK.default_state());
+    assert_eq!(at(&chunk, 4, 3, 9), Blocks::AIR.default_state());
+
+    let mut below = Chunk::new(ChunkCoord { x: 0, y: -1, z: 0 });
+    generator.generate(&mut below);
+    assert_eq!(at(&below, 0, 31, 0), Blocks::STONE.default_state());
+    assert_eq!(at(&below, 0, 30, 0), Blocks::AIR.default_state());
+
+    // Every block but air shows up exactly once
+    let generator = DebugGenerator::new(3);
+    let mut shown = Vec::new();
+    for x in -10..40 {
+        for z in -10..40 {
+            shown.extend(generator.block_at(x, z));
+        }
+    }
+    assert_eq!(shown.len(), Blocks::BLOCKS.len() - 1);
+    assert!(!shown.contains(&Blocks::AIR.default_state()));
+
+    let mut chunk = Chunk::new(ChunkCoord { x: 0, y: 0, z: 0 });
+    generator.generate(&mut chunk);
+    assert_eq!(at(&chunk, 0, 1, 0), Blocks::STONE.default_state());
+    assert_eq!(at(&chunk, 3, 1, 0), Blocks::GRASS_BLOCK.default_state());
+    assert_ne!(at(&chunk, 0, 0, 0), at(&chunk, 1, 0, 0));
+
+    assert!(GeneratorSpec::parse("flat:stone*3,dirt").is_ok());
+    assert!(GeneratorSpec::parse("debug").is_ok());
+    assert!(GeneratorSpec::parse("noise:extra").is_err());
+    assert!(GeneratorSpec::parse("heightmap").is_err());
+    assert!(GeneratorSpec::parse("heightmap:map.png;scale=2,64;origin=0,-5,0").is_ok());
+    assert!(GeneratorSpec::parse("heightmap:map.png;scale=2").is_err());
+    assert!(GeneratorSpec::parse("caves").is_err());
+    assert!(GeneratorSpec::parse("flat:stone*3,dirt")
+        .unwrap()
+        .build(0)
+        .is_ok());
+}
+
+#[test]
+fn noise_config_test() {
+    use super::generator::{
+        config::{DomainWarpConfig, NoiseGeneratorConfig, NoiseType},
+        NoiseGenerator,
+    };
+
+    // The shipped file matches the defaults
+    let shipped = include_str!("../../assets/noise.toml");
+    assert_eq!(
+        NoiseGeneratorConfig::from_toml(shipped).unwrap(),
+        NoiseGeneratorConfig::default()
+    );
+
+    let partial = NoiseGeneratorConfig::from_toml(
+        "cave_threshold = -0.5\n[noise]\nnoise_type = \"perlin\"\n",
+    )
+    .unwrap();
+    assert_eq!(partial.cave_threshold, -0.5);
+    assert_eq!(partial.noise.noise_type, NoiseType::Perlin);
+    assert_eq!(partial.noise.octaves, 4);
+    assert_eq!(partial.base_height, 40.0);
+
+    assert!(NoiseGeneratorConfig::from_toml("scael = 1.0").is_err());
+    assert!(NoiseGeneratorConfig::from_toml("[noise]\nnoise_type = \"voronoi\"").is_err());
+
+    let mut config = NoiseGeneratorConfig::default();
+    config.noise.domain_warp = Some(DomainWarpConfig::default());
+    config.noise.lacunarity = 2.5;
+    assert_eq!(
+        NoiseGeneratorConfig::from_toml(&config.to_toml()).unwrap(),
+        config
+    );
+
+    let plain = NoiseGenerator::new(7);
+    let warped = NoiseGenerator::with_config(7, config);
+    let heights = |generator: &NoiseGenerator| -> Vec<i32> {
+        (0..64)
+            .map(|i| generator.surface_height(i * 5, i * 3))
+            .collect()
+    };
+
+    assert_eq!(heights(&plain), heights(&NoiseGenerator::new(7)));
+    assert_ne!(heights(&plain), heights(&warped));
+    assert!(heights(&plain).iter().all(|h| (-50..=130).contains(h)));
+}
+
+#[test]
+fn density_terrain_test() {
+    use super::generator::{
+        chunk::Chunk,
+        config::{DensityConfig, NoiseGeneratorConfig},
+        voxel::Blocks,
+        Generator, NoiseGenerator,
+    };
+
+    let config = |squash_factor| {
+        let mut config = NoiseGeneratorConfig {
+            cave_threshold: -2.0,
+            vegetation_density: 0.0,
+            density: Some(DensityConfig {
+                squash_factor,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        config.worms.frequency = 0.0;
+        config.ravines.frequency = 0.0;
+        config
+    };
+
+    let generator = NoiseGenerator::with_config(3, config(0.5));
+    let air = Blocks::AIR.default_state();
+    let mut overhangs = 0;
+
+    for cx in 0..3 {
+        for cy in 0..3 {
+            let mut chunk = Chunk::new(ChunkCoord { x: cx, y: cy, z: 0 });
+            generator.generate(&mut chunk);
+
+            let at = |x, y, z| chunk.get_voxel(ChunkLocalCoord { x, y, z }).unwrap();
+
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    for y in 1..CHUNK_SIZE - 1 {
+                        let voxel = at(x, y, z);
+
+                        if voxel == Blocks::GRASS_BLOCK.default_state()
+                            || voxel == Blocks::SAND.default_state()
+                        {
+                            assert_eq!(at(x, y + 1, z), air);
+                        }
+                        if voxel == Blocks::DIRT_BLOCK.default_state() {
+                            assert_ne!(at(x, y + 1, z), air);
+                        }
+                        if voxel == air && at(x, y + 1, z) != air && at(x, y - 1, z) == air {
+                            overhangs += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    assert!(overhangs > 0);
+
+    // Squashed hard enough, density terrain follows the height noise
+    let generator = NoiseGenerator::with_config(3, config(1000.0));
+    let mut chunk = Chunk::new(ChunkCoord { x: 0, y: 0, z: 0 });
+    generator.generate(&mut chunk);
+
+    let mut checked = 0;
+    for x in 0..CHUNK_SIZE {
+        for z in 0..CHUNK_SIZE {
+            let height = generator.surface_height(x as i32, z as i32);
+            if !(0..CHUNK_SIZE as i32 - 1).contains(&height) {
+                continue;
+            }
+
+            let at = |y| chunk.get_voxel(ChunkLocalCoord { x, y, z }).unwrap();
+            assert_ne!(at(height as usize), air);
+            assert_eq!(at(height as usize + 1), air);
+            checked += 1;
+        }
+    }
+    assert!(checked > 0);
+}
+
+#[test]
+fn carver_test() {
+    use super::generator::{
+        carver::{Carver, CarverKind, REGION_SIZE},
+        chunk::Chunk,
+        config::NoiseGeneratorConfig,
+        voxel::Blocks,
+    };
+
+    let config = NoiseGeneratorConfig::default();
+    let worms = Carver::new(CarverKind::Worm, config.worms.clone(), 11);
+    let region = worms.region(-1, 2);
+    assert!(!region.is_empty());
+
+    // Same seed, same tunnels
+    let again = Carver::new(CarverKind::Worm, config.worms.clone(), 11).region(-1, 2);
+    assert_eq!(region.len(), again.len());
+    assert!(region
+        .iter()
+        .zip(again.iter())
+        .all(|(a, b)| a.center == b.center && a.radius == b.radius));
+
+    // Tunnels stay within the neighbouring regions
+    let (min_x, max_x) = (-2 * REGION_SIZE, REGION_SIZE);
+    let (min_z, max_z) = (REGION_SIZE, 4 * REGION_SIZE);
+    assert!(region.iter().all(|carve| {
+        (min_x as f32..max_x as f32).contains(&carve.center[0])
+            && (min_z as f32..max_z as f32).contains(&carve.center[2])
+    }));
+
+    let mut disabled = config.ravines.clone();
+    disabled.frequency = 0.0;
+    assert!(Carver::new(CarverKind::Ravine, disabled, 11)
+        .region(0, 0)
+        .is_empty());
+
+    // A radius wider than a region from a user's config leaves no room to trace
+    let mut wide = config.worms.clone();
+    wide.max_radius = REGION_SIZE as f32 * 2.0;
+    assert!(Carver::new(CarverKind::Worm, wide, 11)
+        .region(0, 0)
+        .is_empty());
+
+    let stone_chunk = |coord: ChunkCoord| {
+        let mut chunk = Chunk::new(coord);
+        for x in 0..CHUNK_SIZE {
+            for y in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    chunk.set_voxel(ChunkLocalCoord { x, y, z }, Blocks::STONE.default_state());
+                }
+            }
+        }
+        chunk
+    };
+
+    // A tunnel crossing a chunk border is carved on both sides of it
+    let crossing = region
+        .iter()
+        .find(|carve| {
+            let left = WorldCoord {
+                x: (carve.center[0] - carve.radius * 0.5).floor() as i32,
+                y: carve.center[1].floor() as i32,
+                z: carve.center[2].floor() as i32,
+            };
+            let right = WorldCoord {
+                x: (carve.center[0] + carve.radius * 0.5).floor() as i32,
+                ..left
+            };
+            carve.radius >= 2.0 && ChunkCoord::from(left) != ChunkCoord::from(right)
+        })
+        .expect("No tunnel crosses a chunk border");
+
+    for offset in [-0.5, 0.5] {
+        let position = WorldCoord {
+            x: (crossing.center[0] + crossing.radius * offset).floor() as i32,
+            y: crossing.center[1].floor() as i32,
+            z: crossing.center[2].floor() as i32,
+        };
+
+        let mut chunk = stone_chunk(position.into());
+        worms.carve(&mut chunk);
+        assert_eq!(
+            chunk.get_voxel(position.into()),
+            Some(Blocks::AIR.default_state())
+        );
+    }
+}
+
+#[test]
+fn vegetation_test() {
+    use super::generator::{
+        chunk::Chunk,
+        feature::{Biome, Feature},
+        vegetation::{Canopy, PlacementRule, Plant, TreeShape, VegetationFeature},
+        voxel::Blocks,
+    };
+
+    let grass = Blocks::GRASS_BLOCK.default_state();
+    let sand = Blocks::SAND.default_state();
+    let log = Blocks::LOG.default_state();
+    let leaves = Blocks::LEAVES.default_state();
+    let air = Blocks::AIR.default_state();
+
+    let rule = PlacementRule::new(&[grass], &[(Biome::Plains, 0.5), (Biome::Forest, 1.0)]);
+    assert_eq!(rule.chance(Biome::Forest, grass), 1.0);
+    assert_eq!(rule.chance(Biome::Desert, grass), 0.0);
+    assert_eq!(rule.chance(Biome::Plains, sand), 0.0);
+
+    let tree = VegetationFeature::new(
+        Plant::Tree(TreeShape {
+            trunk_height: 6..=8,
+            branches: 1..=2,
+            branch_length: 2,
+            canopy: Canopy::Round { radius: 2 },
+            log,
+            leaves,
+        }),
+        PlacementRule::new(&[grass], &[(Biome::Plains, 0.01)]),
+        1,
+    );
+
+    // Trees standing near the top of one chunk grow into the one above
+    let surface = |_: i32, _: i32| 27;
+    let mut below = Chunk::new(ChunkCoord { x: 0, y: 0, z: 0 });
+    let mut above = Chunk::new(ChunkCoord { x: 0, y: 1, z: 0 });
+    tree.place(&mut below, 5, &surface);
+    tree.place(&mut above, 5, &surface);
+
+    let at = |chunk: &Chunk, x, y, z| chunk.get_voxel(ChunkLocalCoord { x, y, z }).unwrap();
+    let mut trunks = 0;
+
+    for x in 0..CHUNK_SIZE {
+        for z in 0..CHUNK_SIZE {
+            // Nothing grows below the surface
+            assert_eq!(at(&below, x, 27, z), air);
+
+            if at(&below, x, 28, z) == log && at(&below, x, 29, z) == log {
+                trunks += 1;
+                assert_ne!(at(&above, x, 0, z), air);
+            }
+        }
+    }
+    assert!(trunks > 0);
+
+    // Single plants don't float over holes
+    let flowers = VegetationFeature::new(
+        Plant::Single(Blocks::POPPY.default_state()),
+        PlacementRule::new(&[grass], &[(Biome::Plains, 1.0)]),
+        2,
+    );
+    let mut chunk = Chunk::new(ChunkCoord { x: 0, y: 0, z: 0 });
+    chunk.set_voxel(ChunkLocalCoord { x: 3, y: 4, z: 3 }, grass);
+    flowers.place(&mut chunk, 5, &|_: i32, _: i32| 4);
+
+    assert_eq!(at(&chunk, 3, 5, 3), Blocks::POPPY.default_state());
+    assert_eq!(at(&chunk, 4, 5, 4), air);
+
+    let mesh = origin_mesh(&[
+        (
+            WorldCoord { x: 2, y: 2, z: 2 },
+            Blocks::TALL_GRASS.default_state(),
+        ),
+        (WorldCoord { x: 5, y: 2, z: 2 }, leaves),
+        (WorldCoord { x: 6, y: 2, z: 2 }, leaves),
+    ]);
+
+    // Two double sided quads for the grass, ten faces for the leaves
+    assert_eq!(mesh.indices.len() / 3, 8 + 20);
+    assert_eq!(mesh.vertices.len(), 8 + 40);
+}
+
+#[test]
+fn block_model_test() {
+    use super::generator::voxel::{Blocks, RegisteredBlock};
+
+    let triangles = |blocks: &[(i32, i32, i32, RegisteredBlock)]| {
+        let blocks: Vec<_> = blocks
+            .iter()
+            .map(|(x, y, z, block)| {
+                (
+                    WorldCoord {
+                        x: *x,
+                        y: *y,
+                        z: *z,
+                    },
+                    block.default_state(),
+                )
+            })
+            .collect();
+
+        origin_mesh(&blocks).indices.len() / 3
+    };
+
+    let stone = Blocks::STONE;
+    let slab = Blocks::STONE_SLAB;
+
+    assert_eq!(triangles(&[(2, 2, 2, slab)]), 12);
+    assert_eq!(triangles(&[(2, 2, 2, Blocks::STONE_STAIRS)]), 24);
+    assert_eq!(triangles(&[(2, 2, 2, Blocks::TABLE)]), 60);
+
+    // Only the full bottom of the slab hides and is hidden by the stone below
+    assert_eq!(triangles(&[(2, 2, 2, slab), (2, 1, 2, stone)]), 20);
+    // Stone above a slab isn't hidden by the slab's top, which is inside the block
+    assert_eq!(triangles(&[(2, 2, 2, slab), (2, 3, 2, stone)]), 24);
+    // Half-height sides don't hide each other
+    assert_eq!(triangles(&[(2, 2, 2, slab), (3, 2, 2, slab)]), 24);
+    assert_eq!(triangles(&[(2, 2, 2, slab), (3, 2, 2, stone)]), 22);
+    // The bottom of the post is hidden, the stone's top isn't
+    assert_eq!(triangles(&[(2, 2, 2, Blocks::FENCE), (2, 1, 2, stone)]), 22);
+
+    // Sides of the slab only show the bottom half of the texture
+    let mesh = origin_mesh(&[(WorldCoord { x: 2, y: 2, z: 2 }, slab.default_state())]);
+    for vertex in mesh.vertices.iter() {
+        if vertex.normal[1] == 0.0 {
+            let v = vertex.uv[1];
+            assert!((0.49..=1.01).contains(&v), "{v}");
+        }
+    }
+}
+
+#[test]
+fn texture_atlas_test() {
+    use super::atlas::{self, AtlasLayout};
+    use super::generator::voxel::Blocks;
+
+    let layout = atlas::layout();
+
+    // Every texture is packed once, blocks sharing a name share the texture
+    assert_eq!(layout.names().len(), 12);
+    let stone = layout.index("stone").unwrap();
+    let slab = Blocks::STONE_SLAB.default_state().id as usize;
+    assert_eq!(layout.block_textures(slab), [stone; 6]);
+
+    let grass = layout.block_textures(Blocks::GRASS_BLOCK.default_state().id as usize);
+    assert_eq!(grass[3], layout.index("dirt").unwrap());
+    assert_ne!(grass[2], grass[0]);
+
+    // UVs of every texture stay inside the atlas without overlapping
+    let step = layout.uv_step();
+    let mut offsets = Vec::new();
+    for index in 0..layout.names().len() {
+        let offset = layout.uv_offset(index);
+        assert!(offset.0 + step.0 <= 1.0001 && offset.1 + step.1 <= 1.0001);
+        assert!(!offsets.contains(&offset));
+        offsets.push(offset);
+    }
+
+    let textures = layout.load("assets/textures/blocks").unwrap();
+    assert_eq!(textures.len(), layout.names().len());
+    // Built into the binary so the game starts from any directory
+    assert_eq!(layout.load_embedded().unwrap(), textures);
+    let image = layout.pack(&textures);
+    let (columns, rows) = layout.grid();
+    assert_eq!(image.dimensions(), (16 * columns as u32, 16 * rows as u32));
+
+    // Exported UVs land on the texture's cell of the packed atlas
+    let dirt = layout.index("dirt").unwrap() as u32;
+    let [u, v] = layout.atlas_uv(dirt, [0.5, 0.5]);
+    let (x, y) = (
+        (u * image.width() as f32) as u32,
+        (v * image.height() as f32) as u32,
+    );
+    assert_eq!(
+        image.get_pixel(x, y),
+        textures[dirt as usize].get_pixel(8, 8)
+    );
+
+    // Textures missing from the directory are all reported
+    let mut block = Blocks::STONE;
+    block.textures = [
+        "stone",
+        "stone",
+        "no_such_texture",
+        "stone",
+        "stone",
+        "other_missing",
+    ];
+    let error = AtlasLayout::from_blocks(&[block])
+        .load("assets/textures/blocks")
+        .unwrap_err();
+    assert!(error.message.contains("no_such_texture.png"), "{error}");
+    assert!(error.message.contains("other_missing.png"), "{error}");
+    let error = AtlasLayout::from_blocks(&[block])
+        .load_embedded()
+        .unwrap_err();
+    assert!(error.message.contains("no_such_texture.png"), "{error}");
+}
+
+#[test]
+fn texture_mip_test() {
+    use super::texture::{MipFilter, TextureConfig};
+    use image::{Rgba, RgbaImage};
+
+    let shipped = std::fs::read_to_string("assets/textures.toml").unwrap();
+    assert_eq!(
+        TextureConfig::from_toml(&shipped).unwrap(),
+        TextureConfig::default()
+    );
+    assert!(TextureConfig::from_toml("mip_filter = \"bicubic\"").is_err());
+
+    // Left half opaque red, right half fully transparent
+    let image = RgbaImage::from_fn(16, 16, |x, _| {
+        if x < 8 {
+            Rgba([200, 0, 0, 255])
+        } else {
+            Rgba([0, 0, 0, 0])
+        }
+    });
+
+    let config = TextureConfig {
+        max_mip_levels: 10,
+        ..Default::default()
+    };
+    let chain = config.mip_chain(&image);
+    let sizes: Vec<_> = chain.iter().map(|level| level.dimensions()).collect();
+    assert_eq!(sizes, [(16, 16), (8, 8), (4, 4), (2, 2), (1, 1)]);
+
+    // Transparent texels don't darken the color, only lower the alpha
+    assert_eq!(chain[4].get_pixel(0, 0), &Rgba([200, 0, 0, 128]));
+    assert_eq!(chain[1].get_pixel(0, 0), &Rgba([200, 0, 0, 255]));
+    assert_eq!(chain[1].get_pixel(7, 0), &Rgba([0, 0, 0, 0]));
+
+    let limited = TextureConfig {
+        max_mip_levels: 2,
+        mip_filter: MipFilter::Lanczos,
+        ..Default::default()
+    };
+    assert_eq!(limited.mip_chain(&image).len(), 3);
+    assert_eq!(
+        TextureConfig {
+            max_mip_levels: 0,
+            ..Default::default()
+        }
+        .mip_chain(&image)
+        .len(),
+        1
+    );
+
+    // Anisotropy only applies with every filter linear
+    let sampler = TextureConfig {
+        anisotropy: 8,
+        ..Default::default()
+    }
+    .sampler_descriptor();
+    assert_eq!(sampler.anisotropy_clamp, 1);
+}
+
+#[test]
+fn packed_vertex_test() {
+    use super::generator::voxel::Blocks;
+    use super::mesh::ChunkVertex;
+
+    assert_eq!(std::mem::size_of::<ChunkVertex>(), 8);
+
+    // Every block model, including the far edges of the chunk
+    let size = CHUNK_SIZE as i32 - 1;
+    let blocks = [
+        (0, 0, 0, Blocks::GRASS_BLOCK),
+        (size, size, size, Blocks::STONE),
+        (size, 0, 5, Blocks::LOG),
+        (4, 4, 4, Blocks::STONE_SLAB),
+        (6, 4, 4, Blocks::STONE_STAIRS),
+        (8, 4, 4, Blocks::FENCE),
+        (10, 4, 4, Blocks::TABLE),
+        (12, 4, 4, Blocks::POPPY),
+        (14, 4, 4, Blocks::LEAVES),
+    ]
+    .map(|(x, y, z, block)| (WorldCoord { x, y, z }, block.default_state()));
+
+    let mesh = origin_mesh(&blocks);
+    assert!(!mesh.vertices.is_empty());
+
+    for vertex in mesh.vertices.iter() {
+        let packed = ChunkVertex::pack(vertex);
+        assert_eq!(packed.unpack(), *vertex);
+        assert_eq!(packed.light(), u8::MAX);
+    }
+
+    let packed = ChunkVertex::pack_mesh(super::mesh::MeshInfo {
+        vertices: mesh.vertices.clone(),
+        indices: mesh.indices.clone(),
+        face_ranges: mesh.face_ranges.clone(),
+    });
+    assert_eq!(packed.indices, mesh.indices);
+    assert_eq!(packed.face_ranges, mesh.face_ranges);
+    assert_eq!(packed.vertices.len(), mesh.vertices.len());
+}
+
+#[test]
+#[allow(clippy::single_range_in_vec_init)]
+fn chunk_buffer_allocator_test() {
+    use super::chunk_buffers::RangeAllocator;
+
+    let mut allocator = RangeAllocator::new(100);
+
+    let a = allocator.allocate(30).unwrap();
+    let b = allocator.allocate(20).unwrap();
+    let c = allocator.allocate(40).unwrap();
+    assert_eq!((a.clone(), b.clone(), c.clone()), (0..30, 30..50, 50..90));
+    assert_eq!(allocator.free_space(), 10);
+    assert!(allocator.allocate(11).is_none());
+
+    // First fit reuses the freed hole
+    allocator.free(a);
+    assert_eq!(allocator.allocate(10), Some(0..10));
+    assert_eq!(allocator.free_ranges(), [10..30, 90..100]);
+
+    // Freed neighbours merge back into one range
+    allocator.free(b);
+    assert_eq!(allocator.free_ranges(), [10..50, 90..100]);
+    allocator.free(c);
+    assert_eq!(allocator.free_ranges(), [10..100]);
+    allocator.free(0..10);
+    assert_eq!(allocator.free_ranges(), [0..100]);
+    assert_eq!(allocator.largest_free(), 100);
+
+    // Fragmented space is packed together by compaction
+    let mut allocator = RangeAllocator::new(100);
+    let ranges: Vec<_> = (0..10).map(|_| allocator.allocate(10).unwrap()).collect();
+    for range in ranges.iter().step_by(2) {
+        allocator.free(range.clone());
+    }
+    assert_eq!(allocator.free_space(), 50);
+    assert!(allocator.allocate(20).is_none());
+
+    let packed = allocator.compact(&[10; 5], 20);
+    assert_eq!(packed, [0..10, 10..20, 20..30, 30..40, 40..50]);
+    assert_eq!(allocator.free_ranges(), [50..100]);
+    assert_eq!(allocator.allocate(20), Some(50..70));
+
+    // Grows when the live ranges and the new one don't fit
+    let packed = allocator.compact(&[50, 20], 60);
+    assert_eq!(packed, [0..50, 50..70]);
+    assert_eq!(allocator.capacity(), 200);
+    assert_eq!(allocator.largest_free(), 130);
+}
+
+#[test]
+fn frustum_test() {
+    use super::camera::Camera;
+    use cgmath::{Point3, Vector3};
+
+    let mut camera = Camera::new(1.0);
+    camera.eye = Point3::new(0.0, 0.0, 0.0);
+    // The view looks along the opposite of `direction`, so this looks towards +Z
+    camera.direction = -Vector3::unit_z();
+    camera.far = 500.0;
+
+    let frustum = camera.frustum();
+    let chunk = |x: f32, y: f32, z: f32| {
+        let min = Vector3::new(x, y, z);
+        frustum.intersects_aabb(min, min + Vector3::new(32.0, 32.0, 32.0))
+    };
+
+    // In front, and around the camera
+    assert!(chunk(-16.0, -16.0, 40.0));
+    assert!(chunk(-16.0, -16.0, -16.0));
+    // Partly inside the edge of the 90 degree field of view
+    assert!(chunk(30.0, -16.0, 40.0));
+
+    // Behind, too far to the side, above and past the far plane
+    assert!(!chunk(-16.0, -16.0, -64.0));
+    assert!(!chunk(200.0, -16.0, 40.0));
+    assert!(!chunk(-16.0, 200.0, 40.0));
+    assert!(!chunk(-16.0, -16.0, 600.0));
+
+    // Turning around swaps what is culled
+    camera.direction = Vector3::unit_z();
+    let frustum = camera.frustum();
+    let min = Vector3::new(-16.0, -16.0, -64.0);
+    assert!(frustum.intersects_aabb(min, min + Vector3::new(32.0, 32.0, 32.0)));
+    let min = Vector3::new(-16.0, -16.0, 40.0);
+    assert!(!frustum.intersects_aabb(min, min + Vector3::new(32.0, 32.0, 32.0)));
+}
+
+#[test]
+fn chunk_connectivity_test() {
+    use super::generator::{
+        chunk::Chunk,
+        visibility::{visible_chunks, ChunkConnectivity},
+        voxel::Blocks,
+    };
+    use std::collections::HashMap;
+
+    let origin = ChunkCoord { x: 0, y: 0, z: 0 };
+    let stone = Blocks::STONE.default_state();
+
+    let air = Box::new(Chunk::new(origin));
+    assert_eq!(ChunkConnectivity::compute(&air), ChunkConnectivity::ALL);
+
+    let mut solid = Box::new(Chunk::new(origin));
+    solid.chunk_data.fill(stone);
+    assert_eq!(ChunkConnectivity::compute(&solid), ChunkConnectivity::NONE);
+
+    // A tunnel along X connects the left and right faces only
+    let mut tunnel = solid.clone();
+    for x in 0..CHUNK_SIZE {
+        tunnel.set_voxel(
+            ChunkLocalCoord { x, y: 4, z: 4 },
+            Blocks::AIR.default_state(),
+        );
+    }
+    let connectivity = ChunkConnectivity::compute(&tunnel);
+    assert!(connectivity.connects(0, 1));
+    assert!(connectivity.connects(1, 0));
+    assert!(!connectivity.connects(0, 2));
+    assert!(!connectivity.connects(4, 5));
+
+    // A solid wall at x = 1 hides everything behind it along the row
+    let walls = HashMap::from([(ChunkCoord { x: 1, y: 0, z: 0 }, ChunkConnectivity::NONE)]);
+    let visible = visible_chunks(origin, 3, |coord| walls.get(&coord).copied());
+
+    assert!(visible.contains(&origin));
+    assert!(visible.contains(&ChunkCoord { x: 1, y: 0, z: 0 }));
+    assert!(!visible.contains(&ChunkCoord { x: 2, y: 0, z: 0 }));
+    assert!(visible.contains(&ChunkCoord { x: 2, y: 1, z: 0 }));
+    assert!(visible.contains(&ChunkCoord { x: -3, y: 0, z: 0 }));
+    assert!(!visible.contains(&ChunkCoord { x: -4, y: 0, z: 0 }));
+}
+
+#[test]
+fn face_direction_test() {
+    use super::generator::{visibility::facing_faces, voxel::Blocks};
+    use super::mesh::ChunkVertex;
+    use cgmath::Vector3;
+
+    let mesh = origin_mesh(&[
+        (
+            WorldCoord { x: 3, y: 3, z: 3 },
+            Blocks::STONE.default_state(),
+        ),
+        (
+            WorldCoord { x: 8, y: 3, z: 3 },
+            Blocks::POPPY.default_state(),
+        ),
+    ]);
+
+    // One quad of the stone block in each direction, the poppy seen from everywhere
+    for (direction, normal) in ChunkVertex::NORMALS.iter().enumerate() {
+        let indices = mesh.face_indices(Some(direction));
+        assert_eq!(indices.len(), 6);
+        assert!(indices
+            .iter()
+            .all(|i| mesh.vertices[*i as usize].normal == *normal));
+    }
+    assert_eq!(mesh.face_indices(None).len(), 24);
+
+    let size = CHUNK_SIZE as f32;
+    let origin = ChunkCoord { x: 0, y: 0, z: 0 };
+    // Inside the chunk every direction can be seen
+    assert_eq!(facing_faces(origin, Vector3::new(1.0, 1.0, 1.0)), [true; 6]);
+    // From left, above and behind only left, top and back faces
+    assert_eq!(
+        facing_faces(origin, Vector3::new(-1.0, size + 1.0, size + 1.0)),
+        [true, false, true, false, true, false]
+    );
+    // On the right side of the chunk the left faces are edge on
+    assert_eq!(
+        facing_faces(origin, Vector3::new(size, 1.0, 1.0)),
+        [false, true, true, true, true, true]
+    );
+}
+
+#[test]
+fn reset_remesh_test() {
+    use super::generator::{chunk::Chunk, World};
+
+    let origin = ChunkCoord { x: 0, y: 0, z: 0 };
+    let load = |world: &mut World<()>| {
+        for coord in [
+            origin,
+            origin.left(),
+            origin.right(),
+            origin.up(),
+            origin.down(),
+            origin.front(),
+            origin.back(),
+        ] {
+            world.insert_chunk(Box::new(Chunk::new(coord)));
+        }
+    };
+
+    let mut world = World::new(());
+    load(&mut world);
+    world.enqueue_meshgen(origin);
+    assert_eq!(world.meshgen_queue_count(), 1);
+
+    // Regenerated chunks have to be meshed again
+    world.reset();
+    load(&mut world);
+    world.enqueue_meshgen(origin);
+    assert_eq!(world.meshgen_queue_count(), 1);
+}
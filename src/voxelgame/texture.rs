@@ -1,6 +1,7 @@
-use std::fmt::Display;
+use std::{fmt::Display, path::Path};
 
-use image::{EncodableLayout, GenericImageView};
+use image::{EncodableLayout, GenericImageView, RgbaImage};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub struct TextureCreateError {
@@ -22,6 +23,161 @@ impl Display for TextureCreateError {
 impl std::error::Error for TextureCreateError {}
 type TextureCreateResult<T> = Result<T, TextureCreateError>;
 
+/// Mirrors `wgpu::FilterMode`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterMode {
+    Nearest,
+    Linear,
+}
+
+impl From<FilterMode> for wgpu::FilterMode {
+    fn from(value: FilterMode) -> Self {
+        match value {
+            FilterMode::Nearest => Self::Nearest,
+            FilterMode::Linear => Self::Linear,
+        }
+    }
+}
+
+/// How each mip level is downsampled from the one above it
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MipFilter {
+    /// Averages every 2x2 block, weighted by alpha so cutouts don't darken
+    Box,
+    Lanczos,
+}
+
+/// Sampling of the terrain textures
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TextureConfig {
+    pub mag_filter: FilterMode,
+    pub min_filter: FilterMode,
+    /// Filtering between mip levels
+    pub mipmap_filter: FilterMode,
+    pub mip_filter: MipFilter,
+    /// Mip levels generated below the full size one, 0 disables mipmaps
+    pub max_mip_levels: u32,
+    /// Anisotropic filtering samples, only used when every filter is linear
+    pub anisotropy: u16,
+}
+
+impl Default for TextureConfig {
+    fn default() -> Self {
+        Self {
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Linear,
+            mip_filter: MipFilter::Box,
+            max_mip_levels: 4,
+            anisotropy: 1,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl TextureConfig {
+    pub fn from_toml(text: &str) -> TextureCreateResult<Self> {
+        toml::from_str(text)
+            .map_err(|e| TextureCreateError::new(format!("Invalid texture config: {e}")))
+    }
+
+    /// Reads the config at `path`, falling back to the defaults if there is no such file
+    pub fn load_or_default(path: impl AsRef<Path>) -> TextureCreateResult<Self> {
+        match std::fs::read_to_string(path.as_ref()) {
+            Ok(text) => Self::from_toml(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(TextureCreateError::new(format!(
+                "Failed to read {}: {e}",
+                path.as_ref().display()
+            ))),
+        }
+    }
+
+    pub fn sampler_descriptor(&self) -> wgpu::SamplerDescriptor<'static> {
+        let linear = [self.mag_filter, self.min_filter, self.mipmap_filter]
+            .iter()
+            .all(|filter| *filter == FilterMode::Linear);
+
+        wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: self.mag_filter.into(),
+            min_filter: self.min_filter.into(),
+            mipmap_filter: self.mipmap_filter.into(),
+            lod_min_clamp: 0.0,
+            lod_max_clamp: self.max_mip_levels as f32,
+            // wgpu rejects anisotropy with any nearest filter
+            anisotropy_clamp: if linear { self.anisotropy.max(1) } else { 1 },
+            ..Default::default()
+        }
+    }
+
+    /// `image` followed by its downsampled mip levels, down to 1x1 or `max_mip_levels`
+    pub fn mip_chain(&self, image: &RgbaImage) -> Vec<RgbaImage> {
+        let mut levels = vec![image.clone()];
+
+        while levels.len() <= self.max_mip_levels as usize {
+            let previous = levels.last().unwrap();
+            if previous.width() == 1 && previous.height() == 1 {
+                break;
+            }
+
+            let (width, height) = (
+                (previous.width() / 2).max(1),
+                (previous.height() / 2).max(1),
+            );
+            let level = match self.mip_filter {
+                MipFilter::Box => box_downsample(previous, width, height),
+                MipFilter::Lanczos => image::imageops::resize(
+                    previous,
+                    width,
+                    height,
+                    image::imageops::FilterType::Lanczos3,
+                ),
+            };
+            levels.push(level);
+        }
+
+        levels
+    }
+}
+
+fn box_downsample(image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    RgbaImage::from_fn(width, height, |x, y| {
+        let mut color = [0.0f32; 3];
+        let mut alpha = 0.0f32;
+        let mut count = 0.0f32;
+
+        for sy in (y * 2)..(y * 2 + 2).min(image.height()) {
+            for sx in (x * 2)..(x * 2 + 2).min(image.width()) {
+                let pixel = image.get_pixel(sx, sy).0;
+                let weight = pixel[3] as f32;
+
+                for (channel, value) in color.iter_mut().zip(pixel) {
+                    *channel += value as f32 * weight;
+                }
+                alpha += weight;
+                count += 1.0;
+            }
+        }
+
+        if alpha == 0.0 {
+            return image::Rgba([0, 0, 0, 0]);
+        }
+
+        image::Rgba([
+            (color[0] / alpha).round() as u8,
+            (color[1] / alpha).round() as u8,
+            (color[2] / alpha).round() as u8,
+            (alpha / count).round() as u8,
+        ])
+    })
+}
+
 #[allow(dead_code)]
 pub struct Texture2d {
     pub texture: wgpu::Texture,
@@ -127,6 +283,78 @@ impl Texture2d {
         texture
     }
 
+    /// Texture array with one layer per image, each with its mip chain
+    /// generated as `config` says. Every layer must be the same size.
+    pub fn from_layers(
+        layers: &[RgbaImage],
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &TextureConfig,
+        label: Option<&str>,
+    ) -> Self {
+        let (width, height) = layers.first().map_or((1, 1), |layer| layer.dimensions());
+        let chains: Vec<_> = layers.iter().map(|layer| config.mip_chain(layer)).collect();
+        let mip_level_count = chains.first().map_or(1, |chain| chain.len() as u32);
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: layers.len().max(1) as u32,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        for (layer, chain) in chains.iter().enumerate() {
+            for (mip_level, image) in chain.iter().enumerate() {
+                queue.write_texture(
+                    wgpu::TexelCopyTextureInfo {
+                        texture: &texture,
+                        mip_level: mip_level as u32,
+                        origin: wgpu::Origin3d {
+                            x: 0,
+                            y: 0,
+                            z: layer as u32,
+                        },
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    image.as_raw(),
+                    wgpu::TexelCopyBufferLayout {
+                        offset: 0,
+                        bytes_per_row: Some(4 * image.width()),
+                        rows_per_image: Some(image.height()),
+                    },
+                    wgpu::Extent3d {
+                        width: image.width(),
+                        height: image.height(),
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&config.sampler_descriptor());
+
+        Self {
+            texture,
+            view,
+            sampler,
+            size,
+        }
+    }
+
     pub fn create_render_texture(
         device: &wgpu::Device,
         config: &wgpu::SurfaceConfiguration,